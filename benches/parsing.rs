@@ -0,0 +1,54 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use qq_group_name_extract::{group::GroupExport, table::Table};
+
+/// A member page with `members` rows, with the same markup as
+/// `https://qun.qq.com/member.html`.
+fn member_page(members: usize) -> String {
+    let mut html = String::from(
+        r#"<html><head><title>群成员 - 测试群 - 12345678</title></head><body>
+<table id="groupMember">
+<tr><th></th><th>序号</th><th>成员</th><th>群昵称</th><th>QQ号</th><th>性别</th><th>Q龄</th><th>入群时间</th><th>最后发言</th><th></th></tr>
+"#,
+    );
+    for i in 0..members {
+        let qq_number = 100_000_000 + i * 7919;
+        let badge = match i {
+            0 => r#"<a class="group-master-a"><i class="icon-group-master"></i></a>"#,
+            1..=4 => r#"<a class="group-manage-a"><i class="icon-group-manage"></i></a>"#,
+            _ => "",
+        };
+        let group_name = if i % 3 == 0 {
+            r#"<span class="white"> </span>"#.to_owned()
+        } else {
+            format!(r#"<span class="white"><span>昵称{i}</span></span>"#)
+        };
+        let gender = ["男", "女", "未知"][i % 3];
+        html.push_str(&format!(
+            r#"<tr class="mb"><td></td><td>{n}</td><td>{badge}
+<img class="" id="useIcon{qq_number}" src="//q4.qlogo.cn/g?b=qq&amp;nk={qq_number}&amp;s=140">
+<span> 成员 {i} </span></td><td>{group_name}</td><td>{qq_number}</td><td>{gender}</td><td>{age}年</td><td>20{year:02}/0{month}/{day:02}</td><td>2022/01/{day:02}</td><td></td></tr>
+"#,
+            n = i + 1,
+            age = i % 20,
+            year = 10 + i % 12,
+            month = 1 + i % 9,
+            day = i % 28 + 1,
+        ));
+    }
+    html.push_str("</table></body></html>");
+    html
+}
+
+fn parsing(c: &mut Criterion) {
+    let html = member_page(100);
+
+    c.bench_function("GroupExport::from_html 100 members", |b| {
+        b.iter(|| GroupExport::from_html(black_box(&html)).unwrap().members)
+    });
+    c.bench_function("Table::find_by_id 100 members", |b| {
+        b.iter(|| Table::find_by_id(black_box(&html), black_box("groupMember")).unwrap())
+    });
+}
+
+criterion_group!(benches, parsing);
+criterion_main!(benches);
@@ -0,0 +1,15 @@
+//! Feeds arbitrary bytes to the html parsers. Errors are fine, only panics are
+//! findings. Run with `cargo fuzz run parse_member`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use qq_group_name_extract::{group::GroupExport, table::Table};
+
+fuzz_target!(|data: &[u8]| {
+    let html = String::from_utf8_lossy(data);
+
+    let _ = GroupExport::from_html(&html);
+    let _ = Table::find_first(&html);
+    let _ = Table::find_by_id(&html, "groupMember");
+});
@@ -0,0 +1,237 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use clap::ArgMatches;
+use eyre::{bail, eyre, Context, Result};
+use regex::Regex;
+use serde::Deserialize;
+use tracing::info;
+
+use crate::{parse_delimiter, parse_qq_number_range, Args, Format, HeaderLang};
+
+/// Default options read from a toml file. Each field mirrors the command line
+/// flag of the same name, which takes precedence when given.
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    recursive_depth: Option<usize>,
+    fail_fast: Option<bool>,
+    table_index: Option<usize>,
+    columns: Option<Vec<String>>,
+    filter_active_within: Option<u32>,
+    filter_inactive_since: Option<u64>,
+    name_contains: Option<String>,
+    name_regex: Option<String>,
+    qq_number_range: Option<String>,
+    filter_tenure_min: Option<u32>,
+    filter_tenure_max: Option<u32>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    include_profile_url: Option<bool>,
+    include_avatar_url: Option<bool>,
+    include_role: Option<bool>,
+    include_days_inactive: Option<bool>,
+    include_index: Option<bool>,
+    append: Option<bool>,
+    no_overwrite: Option<bool>,
+    no_header: Option<bool>,
+    bom: Option<bool>,
+    delimiter: Option<String>,
+    jobs: Option<usize>,
+    anonymize: Option<bool>,
+    split_by_year: Option<bool>,
+    split_by_gender: Option<bool>,
+    format: Option<Format>,
+    header_lang: Option<HeaderLang>,
+    output: Option<PathBuf>,
+    sql_table: Option<String>,
+    template: Option<PathBuf>,
+    summary: Option<bool>,
+    group_name_from_title: Option<bool>,
+    include_group_id: Option<bool>,
+    unique_names: Option<bool>,
+    watch: Option<bool>,
+}
+
+const FILE_NAME: &str = "config.toml";
+
+impl Config {
+    /// Reads the config from `path` if given, or else from the first of
+    /// `./config.toml` and `$XDG_CONFIG_HOME/qq-group-extract/config.toml` that
+    /// exists. Having no config file at all is not an error.
+    pub fn load(path: Option<&Path>) -> Result<Option<Self>> {
+        let path = match path {
+            Some(path) => path.to_owned(),
+            None => match default_paths().into_iter().find(|p| p.is_file()) {
+                Some(path) => path,
+                None => return Ok(None),
+            },
+        };
+
+        info!("Reading config file {path:?}");
+        let text = fs::read_to_string(&path)
+            .wrap_err_with(|| format!("Failed to read config file {path:?}"))?;
+        let config = toml::from_str(&text)
+            .wrap_err_with(|| format!("Failed to parse config file {path:?}"))?;
+        Ok(Some(config))
+    }
+
+    /// Fills in the options of `args` that weren't given on the command line.
+    pub fn apply(self, args: &mut Args, matches: &ArgMatches) -> Result<()> {
+        // argument ids are the kebab-case field names
+        let unset = |id: &str| matches.occurrences_of(id) == 0;
+        // clap only sees `--format` and `--template` conflict when both are
+        // on the command line
+        let format_given = !unset("format") || self.format.is_some();
+
+        if unset("recursive-depth") {
+            args.recursive_depth = args.recursive_depth.or(self.recursive_depth);
+        }
+        if let Some(fail_fast) = self.fail_fast.filter(|_| unset("fail-fast")) {
+            args.fail_fast = fail_fast;
+        }
+        if let Some(table_index) = self.table_index.filter(|_| unset("table-index")) {
+            args.table_index = table_index;
+        }
+        if unset("columns") {
+            args.columns = args.columns.take().or(self.columns);
+        }
+        if unset("filter-active-within") {
+            args.filter_active_within = args.filter_active_within.or(self.filter_active_within);
+        }
+        if unset("filter-inactive-since") {
+            args.filter_inactive_since = args.filter_inactive_since.or(self.filter_inactive_since);
+        }
+        if unset("name-contains") {
+            args.name_contains = args.name_contains.take().or(self.name_contains);
+        }
+        if let Some(name_regex) = self.name_regex.filter(|_| unset("name-regex")) {
+            args.name_regex = Some(
+                Regex::new(&name_regex)
+                    .map_err(|e| eyre!("Invalid `name-regex` in config file: {e}"))?,
+            );
+        }
+        if let Some(range) = self.qq_number_range.filter(|_| unset("qq-number-range")) {
+            args.qq_number_range = Some(
+                parse_qq_number_range(&range)
+                    .map_err(|e| eyre!("Invalid `qq-number-range` in config file: {e}"))?,
+            );
+        }
+        if unset("filter-tenure-min") {
+            args.filter_tenure_min = args.filter_tenure_min.or(self.filter_tenure_min);
+        }
+        if unset("filter-tenure-max") {
+            args.filter_tenure_max = args.filter_tenure_max.or(self.filter_tenure_max);
+        }
+        if let Some(offset) = self.offset.filter(|_| unset("offset")) {
+            args.offset = offset;
+        }
+        if unset("limit") {
+            args.limit = args.limit.or(self.limit);
+        }
+        if let Some(include_profile_url) = self
+            .include_profile_url
+            .filter(|_| unset("include-profile-url"))
+        {
+            args.include_profile_url = include_profile_url;
+        }
+        if let Some(include_avatar_url) = self
+            .include_avatar_url
+            .filter(|_| unset("include-avatar-url"))
+        {
+            args.include_avatar_url = include_avatar_url;
+        }
+        if let Some(include_role) = self.include_role.filter(|_| unset("include-role")) {
+            args.include_role = include_role;
+        }
+        if let Some(include_days_inactive) = self
+            .include_days_inactive
+            .filter(|_| unset("include-days-inactive"))
+        {
+            args.include_days_inactive = include_days_inactive;
+        }
+        if let Some(include_index) = self.include_index.filter(|_| unset("include-index")) {
+            args.include_index = include_index;
+        }
+        if let Some(append) = self.append.filter(|_| unset("append")) {
+            args.append = append;
+        }
+        if let Some(no_overwrite) = self.no_overwrite.filter(|_| unset("no-overwrite")) {
+            args.no_overwrite = no_overwrite;
+        }
+        if let Some(no_header) = self.no_header.filter(|_| unset("no-header")) {
+            args.no_header = no_header;
+        }
+        if let Some(bom) = self.bom.filter(|_| unset("bom")) {
+            args.bom = bom;
+        }
+        if let Some(delimiter) = self.delimiter.filter(|_| unset("delimiter")) {
+            args.delimiter = parse_delimiter(&delimiter)
+                .map_err(|e| eyre!("Invalid `delimiter` in config file: {e}"))?;
+        }
+        if let Some(jobs) = self.jobs.filter(|_| unset("jobs")) {
+            args.jobs = jobs;
+        }
+        if let Some(anonymize) = self.anonymize.filter(|_| unset("anonymize")) {
+            args.anonymize = anonymize;
+        }
+        if let Some(split_by_year) = self.split_by_year.filter(|_| unset("split-by-year")) {
+            args.split_by_year = split_by_year;
+        }
+        if let Some(split_by_gender) = self.split_by_gender.filter(|_| unset("split-by-gender")) {
+            args.split_by_gender = split_by_gender;
+        }
+        if let Some(format) = self.format.filter(|_| unset("format")) {
+            args.format = format;
+        }
+        if let Some(header_lang) = self.header_lang.filter(|_| unset("header-lang")) {
+            args.header_lang = header_lang;
+        }
+        if unset("output") {
+            args.output = args.output.take().or(self.output);
+        }
+        if let Some(sql_table) = self.sql_table.filter(|_| unset("sql-table")) {
+            args.sql_table = sql_table;
+        }
+        if unset("template") {
+            args.template = args.template.take().or(self.template);
+        }
+        if format_given && args.template.is_some() {
+            bail!("`format` and `template` can't both be given");
+        }
+        if let Some(summary) = self.summary.filter(|_| unset("summary")) {
+            args.summary = summary;
+        }
+        if let Some(group_name_from_title) = self
+            .group_name_from_title
+            .filter(|_| unset("group-name-from-title"))
+        {
+            args.group_name_from_title = group_name_from_title;
+        }
+        if let Some(include_group_id) = self.include_group_id.filter(|_| unset("include-group-id"))
+        {
+            args.include_group_id = include_group_id;
+        }
+        if let Some(unique_names) = self.unique_names.filter(|_| unset("unique-names")) {
+            args.unique_names = unique_names;
+        }
+        if let Some(watch) = self.watch.filter(|_| unset("watch")) {
+            args.watch = watch;
+        }
+
+        Ok(())
+    }
+}
+
+fn default_paths() -> Vec<PathBuf> {
+    let mut paths = vec![PathBuf::from(FILE_NAME)];
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")));
+    if let Some(config_home) = config_home {
+        paths.push(config_home.join("qq-group-extract").join(FILE_NAME));
+    }
+    paths
+}
@@ -0,0 +1,104 @@
+//! Reading the bytes of an html export as text.
+
+use std::{borrow::Cow, fs, io, path::Path, str::Utf8Error};
+
+use encoding_rs::{Encoding, GB18030, GBK};
+use tracing::{debug, warn};
+
+use crate::error::QqExtractError;
+
+/// Decodes raw html bytes into a string.
+///
+/// Older QQ export tools save GBK (or GB2312) html, which is detected from its
+/// `<meta charset>` and transcoded. Everything else is read as UTF-8, without a
+/// leading BOM. Fails if the html is neither GBK nor valid UTF-8.
+pub fn decode_html(bytes: &[u8]) -> Result<Cow<'_, str>, Utf8Error> {
+    if let Some(encoding) = sniff_charset(bytes).filter(|&e| e == GBK || e == GB18030) {
+        debug!("Decoding html as {}", encoding.name());
+        let (decoded, _, had_errors) = encoding.decode(bytes);
+        if had_errors {
+            warn!("Malformed {} sequences were replaced", encoding.name());
+        }
+        return Ok(decoded);
+    }
+
+    let s = std::str::from_utf8(bytes)?;
+    Ok(Cow::Borrowed(strip_bom(s)))
+}
+
+/// Reads the html file at `path` and decodes it with [`decode_html`].
+///
+/// Html that is neither GBK nor UTF-8 is reported as an
+/// [`io::ErrorKind::InvalidData`] error on the file.
+pub fn read_html_file(path: &Path) -> Result<String, QqExtractError> {
+    let io_error = |source| QqExtractError::Io {
+        path: path.to_owned(),
+        source,
+    };
+    let bytes = fs::read(path).map_err(io_error)?;
+    let html =
+        decode_html(&bytes).map_err(|e| io_error(io::Error::new(io::ErrorKind::InvalidData, e)))?;
+    Ok(html.into_owned())
+}
+
+/// Strips a leading UTF-8 BOM, which some Windows tools put in front of the html
+fn strip_bom(s: &str) -> &str {
+    s.strip_prefix('\u{feff}').unwrap_or(s)
+}
+
+/// Finds the encoding named by `charset=` in the first 1024 bytes, which is
+/// where browsers look for it too
+fn sniff_charset(bytes: &[u8]) -> Option<&'static Encoding> {
+    const NEEDLE: &[u8] = b"charset=";
+
+    let head = &bytes[..bytes.len().min(1024)];
+    let start = head
+        .windows(NEEDLE.len())
+        .position(|w| w.eq_ignore_ascii_case(NEEDLE))?
+        + NEEDLE.len();
+    let label: Vec<u8> = head[start..]
+        .iter()
+        .skip_while(|&&b| b == b'"' || b == b'\'')
+        .take_while(|&&b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+        .copied()
+        .collect();
+
+    Encoding::for_label(&label)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::group::GroupExport;
+
+    const SAMPLE_GROUP: &[u8] = include_bytes!("../tests/fixtures/sample_group.html");
+
+    #[test]
+    fn bom_is_stripped() {
+        let bom = include_bytes!("../tests/fixtures/sample_group_bom.html");
+        assert!(bom.starts_with(b"\xEF\xBB\xBF"));
+
+        let html = decode_html(bom).unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert_eq!(html, decode_html(SAMPLE_GROUP).unwrap());
+        assert_eq!(
+            GroupExport::from_html(&html).unwrap(),
+            GroupExport::from_html(&decode_html(SAMPLE_GROUP).unwrap()).unwrap()
+        );
+    }
+
+    #[test]
+    fn gbk_is_transcoded() {
+        let gbk = include_bytes!("../tests/fixtures/sample_group_gbk.html");
+        let html = decode_html(gbk).unwrap();
+        assert!(html.contains(r#"<meta charset="gbk">"#));
+        let export = GroupExport::from_html(&html).unwrap();
+        assert_eq!(export.group_name.as_deref(), Some("读书会"));
+        assert_eq!(
+            export.members,
+            GroupExport::from_html(&decode_html(SAMPLE_GROUP).unwrap())
+                .unwrap()
+                .members
+        );
+    }
+}
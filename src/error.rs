@@ -0,0 +1,224 @@
+//! The error types of the member parsing functions and the output writers.
+
+use std::{error::Error, fmt, io, path::PathBuf};
+
+/// The error returned when members can't be parsed out of an html export, see
+/// [`GroupExport::from_html`](crate::group::GroupExport::from_html).
+#[derive(Debug)]
+pub enum QqExtractError {
+    /// The page has no member table.
+    TableNotFound,
+    /// A row of the member table has no cell under `header`. `row` counts from
+    /// 0, not including the header row.
+    MissingCell { row: usize, header: &'static str },
+    /// The `性别` cell isn't one of `男`, `女` or `未知`.
+    InvalidGender(String),
+    /// The `QQ号` cell isn't a string of digits.
+    InvalidQqNumber(String),
+    /// A date isn't in the `2021/11/01` format of the member page.
+    InvalidDate(String),
+    /// A cell doesn't have the markup it should.
+    ParseError(String),
+    /// Parsing row `row` of the member table failed. `row` counts as in
+    /// [`MissingCell`](QqExtractError::MissingCell).
+    InRow {
+        row: usize,
+        source: Box<QqExtractError>,
+    },
+    /// Writing the csv of
+    /// [`Member::parse_html_into`](crate::qqtable::Member::parse_html_into)
+    /// failed.
+    Csv(csv::Error),
+    /// Reading the file or directory at `path` failed, or the file is neither
+    /// GBK nor UTF-8.
+    Io { path: PathBuf, source: io::Error },
+    /// Parsing the file at `path` failed.
+    InFile {
+        path: PathBuf,
+        source: Box<QqExtractError>,
+    },
+    /// Writing the members to `path` failed.
+    Write { path: PathBuf, source: OutputError },
+}
+
+impl fmt::Display for QqExtractError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QqExtractError::TableNotFound => write!(f, "failed to find the member table"),
+            QqExtractError::MissingCell { row, header } => {
+                write!(
+                    f,
+                    "failed to get value for header `{header}`, at row `{row}`"
+                )
+            }
+            QqExtractError::InvalidGender(gender) => write!(f, "unrecognized gender `{gender}`"),
+            QqExtractError::InvalidQqNumber(qq_number) => {
+                write!(f, "invalid QQ number `{qq_number}`")
+            }
+            QqExtractError::InvalidDate(date) => write!(f, "unrecognized date `{date}`"),
+            QqExtractError::ParseError(message) => write!(f, "{message}"),
+            QqExtractError::InRow { row, .. } => write!(f, "failed to parse row `{row}`"),
+            QqExtractError::Csv(e) => write!(f, "failed to write csv: {e}"),
+            QqExtractError::Io { path, .. } => write!(f, "failed to read `{}`", path.display()),
+            QqExtractError::InFile { path, .. } => {
+                write!(f, "failed to parse `{}`", path.display())
+            }
+            QqExtractError::Write { path, .. } => {
+                write!(f, "failed to write `{}`", path.display())
+            }
+        }
+    }
+}
+
+impl Error for QqExtractError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            QqExtractError::Csv(e) => Some(e),
+            QqExtractError::Io { source, .. } => Some(source),
+            QqExtractError::InRow { source, .. } | QqExtractError::InFile { source, .. } => {
+                Some(source.as_ref())
+            }
+            QqExtractError::Write { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<csv::Error> for QqExtractError {
+    fn from(e: csv::Error) -> Self {
+        QqExtractError::Csv(e)
+    }
+}
+
+/// The error returned when members can't be written by one of the writers in
+/// [`output`](crate::output).
+#[derive(Debug)]
+pub enum OutputError {
+    /// Writing to the writer or file failed.
+    Io(io::Error),
+    /// Writing or reading back a csv file failed.
+    Csv(csv::Error),
+    /// The csv file being appended to has a different header than the one that
+    /// would be written.
+    HeaderMismatch {
+        existing: Vec<String>,
+        expected: Vec<String>,
+    },
+    /// A member couldn't be written as JSON.
+    Json(serde_json::Error),
+    /// The table name given to
+    /// [`write_sql`](crate::output::sql::write_sql) isn't a plain identifier.
+    InvalidTableName(String),
+    /// The Handlebars template doesn't parse.
+    Template(Box<handlebars::TemplateError>),
+    /// Rendering the Handlebars template failed, e.g. because it refers to a
+    /// field members don't have.
+    Render(Box<handlebars::RenderError>),
+    /// Writing the OpenDocument spreadsheet failed.
+    Ods(spreadsheet_ods::OdsError),
+    /// Writing the parquet file failed.
+    Parquet(parquet::errors::ParquetError),
+    /// Writing the Arrow IPC file failed.
+    #[cfg(not(target_arch = "wasm32"))]
+    Arrow(arrow2::error::Error),
+}
+
+impl fmt::Display for OutputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputError::Io(_) => write!(f, "failed to write output"),
+            OutputError::Csv(_) => write!(f, "failed to write csv"),
+            OutputError::HeaderMismatch { existing, expected } => write!(
+                f,
+                "can't append to a csv file whose header {existing:?} differs from {expected:?}"
+            ),
+            OutputError::Json(_) => write!(f, "failed to write member as JSON"),
+            OutputError::InvalidTableName(name) => write!(f, "invalid SQL table name `{name}`"),
+            OutputError::Template(_) => write!(f, "failed to parse template"),
+            OutputError::Render(_) => write!(f, "failed to render template"),
+            OutputError::Ods(_) => write!(f, "failed to write spreadsheet"),
+            OutputError::Parquet(_) => write!(f, "failed to write parquet file"),
+            #[cfg(not(target_arch = "wasm32"))]
+            OutputError::Arrow(_) => write!(f, "failed to write arrow file"),
+        }
+    }
+}
+
+impl Error for OutputError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            OutputError::Io(e) => Some(e),
+            OutputError::Csv(e) => Some(e),
+            OutputError::HeaderMismatch { .. } => None,
+            OutputError::Json(e) => Some(e),
+            OutputError::InvalidTableName(_) => None,
+            OutputError::Template(e) => Some(e.as_ref()),
+            OutputError::Render(e) => Some(e.as_ref()),
+            OutputError::Ods(e) => Some(e),
+            OutputError::Parquet(e) => Some(e),
+            #[cfg(not(target_arch = "wasm32"))]
+            OutputError::Arrow(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for OutputError {
+    fn from(e: io::Error) -> Self {
+        OutputError::Io(e)
+    }
+}
+
+impl From<csv::Error> for OutputError {
+    fn from(e: csv::Error) -> Self {
+        OutputError::Csv(e)
+    }
+}
+
+impl From<serde_json::Error> for OutputError {
+    fn from(e: serde_json::Error) -> Self {
+        OutputError::Json(e)
+    }
+}
+
+impl From<handlebars::TemplateError> for OutputError {
+    fn from(e: handlebars::TemplateError) -> Self {
+        OutputError::Template(Box::new(e))
+    }
+}
+
+impl From<handlebars::RenderError> for OutputError {
+    fn from(e: handlebars::RenderError) -> Self {
+        OutputError::Render(Box::new(e))
+    }
+}
+
+impl From<spreadsheet_ods::OdsError> for OutputError {
+    fn from(e: spreadsheet_ods::OdsError) -> Self {
+        OutputError::Ods(e)
+    }
+}
+
+impl From<parquet::errors::ParquetError> for OutputError {
+    fn from(e: parquet::errors::ParquetError) -> Self {
+        OutputError::Parquet(e)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<arrow2::error::Error> for OutputError {
+    fn from(e: arrow2::error::Error) -> Self {
+        OutputError::Arrow(e)
+    }
+}
+
+/// Formats `err` followed by each of its causes, separated by `: `, for
+/// bindings and log lines that can only pass on a message.
+pub fn display_chain(err: &dyn Error) -> String {
+    let mut message = err.to_string();
+    let mut source = err.source();
+    while let Some(cause) = source {
+        message.push_str(&format!(": {cause}"));
+        source = cause.source();
+    }
+    message
+}
@@ -0,0 +1,159 @@
+//! Selecting members by their fields, e.g. for `--filter-inactive-since`.
+
+use std::ops::Range;
+
+use chrono::{Duration, NaiveDate};
+use regex::Regex;
+
+use crate::qqtable::Member;
+
+/// A set of conditions on members, built up with the methods below. A member
+/// matches if it meets all of them, so the default filter matches everyone.
+///
+/// ```
+/// # use chrono::NaiveDate;
+/// # use qq_group_name_extract::filter::MemberFilter;
+/// let today = NaiveDate::from_ymd_opt(2022, 5, 18).unwrap();
+/// let filter = MemberFilter::new(today).inactive_since(365);
+/// ```
+#[derive(Debug, Clone)]
+pub struct MemberFilter {
+    today: NaiveDate,
+    /// Members must have last spoken no longer ago than this.
+    active_within: Option<Duration>,
+    /// Members must have last spoken before this day.
+    inactive_before: Option<NaiveDate>,
+    /// Lowercase text that the QQ name or group nickname must contain.
+    name_contains: Option<String>,
+    /// Pattern that the QQ name or group nickname must match.
+    name_regex: Option<Regex>,
+    qq_number_range: Option<Range<u64>>,
+    /// Least and most complete years since members joined.
+    tenure_min: Option<u32>,
+    tenure_max: Option<u32>,
+}
+
+impl MemberFilter {
+    /// Returns a filter that matches every member, counting days up to
+    /// `today`.
+    pub fn new(today: NaiveDate) -> Self {
+        Self {
+            today,
+            active_within: None,
+            inactive_before: None,
+            name_contains: None,
+            name_regex: None,
+            qq_number_range: None,
+            tenure_min: None,
+            tenure_max: None,
+        }
+    }
+
+    /// Includes members who have spoken within the last `days` days, as
+    /// [`Member::is_active`] but counting up to the filter's `today`. Members
+    /// without a last spoken date are left out.
+    pub fn active_within(mut self, days: u32) -> Self {
+        self.active_within = Some(Duration::days(days.into()));
+        self
+    }
+
+    /// Includes members inactive for at least `days` days: those whose
+    /// [`Member::days_since_last_spoken`] is more than `days`, or who have no
+    /// last spoken date.
+    pub fn inactive_since(mut self, days: u64) -> Self {
+        let days = i64::try_from(days).unwrap_or(i64::MAX);
+        self.inactive_before = Some(
+            Duration::try_days(days)
+                .and_then(|days| self.today.checked_sub_signed(days))
+                .unwrap_or(NaiveDate::MIN),
+        );
+        self
+    }
+
+    /// The day members must have last spoken before to pass
+    /// [`inactive_since`](Self::inactive_since), if it was given.
+    pub fn inactive_cutoff(&self) -> Option<NaiveDate> {
+        self.inactive_before
+    }
+
+    /// Includes members whose QQ name or group nickname contains `s`,
+    /// ignoring case.
+    pub fn name_contains(mut self, s: &str) -> Self {
+        self.name_contains = Some(s.to_lowercase());
+        self
+    }
+
+    /// Includes members whose QQ name or group nickname matches `re` anywhere.
+    pub fn name_matches(mut self, re: &Regex) -> Self {
+        self.name_regex = Some(re.clone());
+        self
+    }
+
+    /// Includes members whose QQ number is at least `start` and less than
+    /// `end`. QQ numbers that aren't numbers are never in range.
+    pub fn qq_number_range(mut self, start: u64, end: u64) -> Self {
+        self.qq_number_range = Some(start..end);
+        self
+    }
+
+    /// Includes members who joined at least `years` complete years ago, see
+    /// [`Member::joined_years_ago`]. Members with an unknown join date are
+    /// left out.
+    pub fn tenure_min(mut self, years: u32) -> Self {
+        self.tenure_min = Some(years);
+        self
+    }
+
+    /// Includes members who joined at most `years` complete years ago, see
+    /// [`Member::joined_years_ago`]. Members with an unknown join date are
+    /// left out.
+    pub fn tenure_max(mut self, years: u32) -> Self {
+        self.tenure_max = Some(years);
+        self
+    }
+
+    /// Returns `true` if `member` meets all the conditions of the filter.
+    pub fn matches(&self, member: &Member) -> bool {
+        if let Some(threshold) = self.active_within {
+            if member
+                .last_spoken_date
+                .is_none_or(|date| self.today - date > threshold)
+            {
+                return false;
+            }
+        }
+        if let Some(cutoff) = self.inactive_before {
+            if member.last_spoken_date.is_some_and(|date| date >= cutoff) {
+                return false;
+            }
+        }
+        let names = [&member.qq_name, &member.group_name];
+        if let Some(s) = &self.name_contains {
+            if !names.iter().any(|name| name.to_lowercase().contains(s)) {
+                return false;
+            }
+        }
+        if let Some(re) = &self.name_regex {
+            if !names.iter().any(|name| re.is_match(name)) {
+                return false;
+            }
+        }
+        if let Some(range) = &self.qq_number_range {
+            match member.qq_number.parse() {
+                Ok(number) if range.contains(&number) => {}
+                _ => return false,
+            }
+        }
+        if self.tenure_min.is_some() || self.tenure_max.is_some() {
+            let Some(years) = member.joined_years_ago(self.today) else {
+                return false;
+            };
+            if self.tenure_min.is_some_and(|min| years < min)
+                || self.tenure_max.is_some_and(|max| years > max)
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
@@ -0,0 +1,335 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+use chrono::{Datelike, NaiveDate};
+
+use scraper::{Html, Selector};
+use serde::Serialize;
+use tracing::debug;
+use walkdir::WalkDir;
+
+use crate::{
+    decode::read_html_file,
+    error::QqExtractError,
+    qqtable::{Gender, Member, ParseConfig, Role},
+};
+
+/// The members of one QQ group, as parsed from an html export of
+/// `https://qun.qq.com/member.html`, along with where they came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupExport {
+    /// The group number, if the page mentions it.
+    pub group_id: Option<String>,
+    /// The name of the group, if the page title has it.
+    pub group_name: Option<String>,
+    pub members: Vec<Member>,
+    /// The file the html was read from, if any.
+    pub source_path: Option<PathBuf>,
+}
+
+/// The changes between two exports of a group, see [`GroupExport::diff`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct GroupDiff<'a> {
+    /// Members only in the later export.
+    pub joined: Vec<&'a Member>,
+    /// Members only in the earlier export.
+    pub left: Vec<&'a Member>,
+    /// Members whose group nickname changed, as `(before, after)`.
+    pub renamed: Vec<(&'a Member, &'a Member)>,
+}
+
+impl GroupDiff<'_> {
+    /// Returns `true` if nothing changed.
+    pub fn is_empty(&self) -> bool {
+        self.joined.is_empty() && self.left.is_empty() && self.renamed.is_empty()
+    }
+}
+
+/// Summary statistics of a group, see [`GroupExport::stats`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct GroupStats {
+    /// The group number, as in [`GroupExport::group_id`].
+    pub group_id: Option<String>,
+    pub member_count: usize,
+    pub male_count: usize,
+    pub female_count: usize,
+    pub unknown_gender_count: usize,
+    /// The owner and the admins.
+    pub admin_count: usize,
+    /// How many of the members joined in each year.
+    pub joined_per_year: BTreeMap<i32, usize>,
+    /// Members whose join date is unknown.
+    pub unknown_joined_count: usize,
+    pub first_joined: Option<NaiveDate>,
+    pub last_joined: Option<NaiveDate>,
+    /// The most recent day anyone spoke.
+    pub last_spoken: Option<NaiveDate>,
+}
+
+static TITLE_SLT: LazyLock<Selector> = LazyLock::new(|| Selector::parse("title").unwrap());
+static GROUP_ID_META_SLT: LazyLock<Selector> =
+    LazyLock::new(|| Selector::parse(r#"meta[name="group-id"]"#).unwrap());
+static SCRIPT_SLT: LazyLock<Selector> = LazyLock::new(|| Selector::parse("script").unwrap());
+
+impl GroupExport {
+    /// Parses an html export of `https://qun.qq.com/member.html`: the members
+    /// of its `groupMember` table, and the group number and name from the
+    /// page, if it has them.
+    pub fn from_html(html: &str) -> Result<Self, QqExtractError> {
+        GroupExport::from_document(&Html::parse_document(html), &ParseConfig::DEFAULT)
+    }
+
+    /// Like [`GroupExport::from_html`], but see [`Member::from_html_or_nth`].
+    pub fn from_html_or_nth(html: &str, table_index: usize) -> Result<Self, QqExtractError> {
+        GroupExport::from_document(
+            &Html::parse_document(html),
+            &ParseConfig::or_nth(table_index),
+        )
+    }
+
+    /// Reads the members and the group info from the same parsed page.
+    fn from_document(document: &Html, config: &ParseConfig) -> Result<Self, QqExtractError> {
+        let (group_id, group_name) = group_info(document);
+        Ok(GroupExport {
+            group_id,
+            group_name,
+            members: Member::from_document(document, config)?,
+            source_path: None,
+        })
+    }
+
+    /// Reads and parses the html export at `path`, in UTF-8 or GBK, see
+    /// [`decode_html`].
+    ///
+    /// [`decode_html`]: crate::decode::decode_html
+    pub fn from_file(path: &Path) -> Result<Self, QqExtractError> {
+        GroupExport::parse_file(path, GroupExport::from_html)
+    }
+
+    /// Like [`GroupExport::from_file`], but see [`Member::from_html_or_nth`].
+    pub fn from_file_or_nth(path: &Path, table_index: usize) -> Result<Self, QqExtractError> {
+        GroupExport::parse_file(path, |html| {
+            GroupExport::from_html_or_nth(html, table_index)
+        })
+    }
+
+    /// Reads the html file at `path` and parses it with `parse`, adding the
+    /// path to the export, or to the error if it doesn't parse.
+    fn parse_file(
+        path: &Path,
+        parse: impl FnOnce(&str) -> Result<Self, QqExtractError>,
+    ) -> Result<Self, QqExtractError> {
+        let html = read_html_file(path)?;
+
+        parse(&html)
+            .map(|export| export.with_source_path(path))
+            .map_err(|e| QqExtractError::InFile {
+                path: path.to_owned(),
+                source: Box::new(e),
+            })
+    }
+
+    /// Parses each `.html` file under `dir`, at any depth, as it is iterated.
+    ///
+    /// A file that fails to parse, or a directory that can't be read, gives an
+    /// `Err` without stopping the iteration, so that the caller can decide
+    /// whether to skip it or stop.
+    pub fn from_dir(dir: &Path) -> impl Iterator<Item = Result<Self, QqExtractError>> {
+        let dir = dir.to_owned();
+        WalkDir::new(&dir)
+            .into_iter()
+            .filter_map(move |entry| match entry {
+                Ok(entry) => {
+                    let path = entry.path();
+                    let is_html = path.extension().is_some_and(|ext| ext == "html");
+                    (entry.file_type().is_file() && is_html).then(|| GroupExport::from_file(path))
+                }
+                Err(e) => Some(Err(QqExtractError::Io {
+                    path: e.path().unwrap_or(&dir).to_owned(),
+                    source: e.into(),
+                })),
+            })
+    }
+
+    /// Compares two exports of the same group by QQ number.
+    pub fn diff<'a>(before: &'a GroupExport, after: &'a GroupExport) -> GroupDiff<'a> {
+        let by_qq_number = |export: &'a GroupExport| -> HashMap<&'a str, &'a Member> {
+            export
+                .members
+                .iter()
+                .map(|m| (m.qq_number.as_str(), m))
+                .collect()
+        };
+        let before_members = by_qq_number(before);
+        let after_members = by_qq_number(after);
+
+        GroupDiff {
+            joined: after
+                .members
+                .iter()
+                .filter(|m| !before_members.contains_key(m.qq_number.as_str()))
+                .collect(),
+            left: before
+                .members
+                .iter()
+                .filter(|m| !after_members.contains_key(m.qq_number.as_str()))
+                .collect(),
+            renamed: before
+                .members
+                .iter()
+                .filter_map(|b| {
+                    after_members
+                        .get(b.qq_number.as_str())
+                        .filter(|a| a.group_name != b.group_name)
+                        .map(|&a| (b, a))
+                })
+                .collect(),
+        }
+    }
+
+    /// Finds the members who are in every one of `groups`, matched by QQ
+    /// number, in the order of the first group.
+    pub fn cross_reference(groups: &[GroupExport]) -> Vec<&Member> {
+        let Some((first, rest)) = groups.split_first() else {
+            return Vec::new();
+        };
+        let others: Vec<HashSet<&str>> = rest
+            .iter()
+            .map(|group| group.members.iter().map(|m| m.qq_number.as_str()).collect())
+            .collect();
+        first
+            .members
+            .iter()
+            .filter(|m| others.iter().all(|set| set.contains(m.qq_number.as_str())))
+            .collect()
+    }
+
+    /// Counts the members by gender, role and join year.
+    pub fn stats(&self) -> GroupStats {
+        let mut stats = GroupStats {
+            group_id: self.group_id.clone(),
+            member_count: self.members.len(),
+            ..GroupStats::default()
+        };
+        for member in &self.members {
+            match member.gender {
+                Gender::Male => stats.male_count += 1,
+                Gender::Female => stats.female_count += 1,
+                Gender::Unknown => stats.unknown_gender_count += 1,
+            }
+            if member.role != Role::Member {
+                stats.admin_count += 1;
+            }
+            match member.joined_date {
+                Some(date) => *stats.joined_per_year.entry(date.year()).or_default() += 1,
+                None => stats.unknown_joined_count += 1,
+            }
+        }
+
+        let joined = self.members.iter().filter_map(|m| m.joined_date);
+        stats.first_joined = joined.clone().min();
+        stats.last_joined = joined.max();
+        stats.last_spoken = self.members.iter().filter_map(|m| m.last_spoken_date).max();
+        stats
+    }
+
+    /// Finds the QQ names shared by more than one member, with the members
+    /// sharing each name in page order.
+    pub fn duplicate_names(&self) -> BTreeMap<&str, Vec<&Member>> {
+        let mut by_name: BTreeMap<&str, Vec<&Member>> = BTreeMap::new();
+        for member in &self.members {
+            by_name.entry(&member.qq_name).or_default().push(member);
+        }
+        by_name.retain(|_, members| members.len() > 1);
+        by_name
+    }
+
+    pub fn with_source_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.source_path = Some(path.into());
+        self
+    }
+}
+
+/// Finds the group number and name outside of the member table.
+///
+/// The number is taken from a `<meta name="group-id">`, a `groupId` variable
+/// in a script, or else the title, in that order. The name only appears in the
+/// title.
+fn group_info(html: &Html) -> (Option<String>, Option<String>) {
+    let title = page_title(html);
+
+    let group_id = group_id_from_meta(html)
+        .or_else(|| group_id_from_scripts(html))
+        .or_else(|| title.as_deref().and_then(group_id_from_title));
+    let group_name = title.as_deref().and_then(group_name_from_title);
+    (group_id, group_name)
+}
+
+/// The text of the page `<title>`, which reads like
+/// `群成员 - 我的QQ群 - 12345678`.
+fn page_title(html: &Html) -> Option<String> {
+    let title: String = html.select(&TITLE_SLT).next()?.text().collect();
+    debug!("Page title: {title:?}");
+    Some(title)
+}
+
+fn group_id_from_meta(html: &Html) -> Option<String> {
+    let content = html
+        .select(&GROUP_ID_META_SLT)
+        .next()?
+        .value()
+        .attr("content")?;
+    let content = content.trim();
+    is_group_id(content).then(|| content.to_owned())
+}
+
+/// Looks for an assignment like `var groupId = "12345678"` or
+/// `groupId: 12345678` in the inline scripts of the page.
+fn group_id_from_scripts(html: &Html) -> Option<String> {
+    const NEEDLE: &str = "groupId";
+
+    html.select(&SCRIPT_SLT).find_map(|script| {
+        let text: String = script.text().collect();
+        text.match_indices(NEEDLE).find_map(|(i, _)| {
+            let value = text[i + NEEDLE.len()..]
+                .trim_start_matches(|c: char| c.is_whitespace() || "=:'\"".contains(c));
+            let end = value
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(value.len());
+            is_group_id(&value[..end]).then(|| value[..end].to_owned())
+        })
+    })
+}
+
+/// Group numbers have at least 5 digits.
+fn is_group_id(s: &str) -> bool {
+    s.len() >= 5 && s.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Looks for the group number in the page title.
+fn group_id_from_title(title: &str) -> Option<String> {
+    title
+        .split(|c: char| !c.is_ascii_digit())
+        .find(|digits| is_group_id(digits))
+        .map(str::to_owned)
+}
+
+/// Takes the group name out of the page title, i.e. what is left after
+/// removing the leading `群成员` and the trailing group number.
+fn group_name_from_title(title: &str) -> Option<String> {
+    let mut parts: Vec<_> = title.split(" - ").map(str::trim).collect();
+    if parts.first() == Some(&"群成员") {
+        parts.remove(0);
+    }
+    if parts
+        .last()
+        .is_some_and(|p| !p.is_empty() && p.bytes().all(|b| b.is_ascii_digit()))
+    {
+        parts.pop();
+    }
+    let name = parts.join(" - ");
+    (!name.is_empty()).then_some(name)
+}
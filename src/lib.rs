@@ -1,2 +1,11 @@
 pub mod table;
-pub mod qqtable;
\ No newline at end of file
+pub mod qqtable;
+pub mod group;
+pub mod filter;
+pub mod output;
+pub mod error;
+pub mod decode;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "wasm")]
+pub mod wasm;
\ No newline at end of file
@@ -1,101 +1,1644 @@
+// The command line program reads and writes files, so it is left out of wasm
+// builds, which only need the library.
+#![cfg_attr(target_arch = "wasm32", no_main)]
+#![cfg(not(target_arch = "wasm32"))]
+
+mod config;
+mod manifest;
+
 use std::{
-    fs::File,
-    io::Read,
+    borrow::Cow,
+    collections::HashMap,
+    fmt,
+    fs::{self, File, OpenOptions},
+    hash::Hash,
+    io::{self, BufWriter, IsTerminal, Write},
+    ops::Range,
     path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc,
+    },
 };
 
-use clap::Parser;
-use csv::Writer;
-use eyre::{Context, Result};
-use log::{info, warn};
-use qq_group_name_extract::qqtable::Member;
+use chrono::{Datelike, Local};
+use clap::{ArgEnum, CommandFactory, FromArgMatches, Parser, Subcommand};
+use config::Config;
+use csv::{ReaderBuilder, WriterBuilder};
+use eyre::{bail, eyre, Context, Result};
+use indicatif::{ProgressBar, ProgressStyle};
+use notify_debouncer_mini::{new_debouncer, notify::RecursiveMode};
+use qq_group_name_extract::{
+    decode::read_html_file,
+    error::{display_chain, OutputError, QqExtractError},
+    filter::MemberFilter,
+    group::GroupExport,
+    output::{
+        arrow::write_arrow_ipc,
+        latex::write_latex,
+        ndjson::write_ndjson,
+        ods::write_ods,
+        parquet::write_parquet,
+        sql::write_sql,
+        template::{write_html_table, write_template},
+        terminal::write_terminal_table,
+    },
+    qqtable::{format_qq_date, ColumnNames, Gender, Member},
+};
+use rayon::{prelude::*, ThreadPoolBuilder};
+use regex::Regex;
+use serde::Deserialize;
+use tracing::{debug, error, info, instrument, warn};
+use tracing_subscriber::{
+    filter::{LevelFilter, Targets},
+    prelude::*,
+};
+use uuid::Uuid;
 use walkdir::WalkDir;
 
 /// Program to extract QQ group names and related info from an html table pasted from `https://qun.qq.com/member.html`
 #[derive(Parser, Debug)]
-#[clap(about, long_about = None)]
+#[clap(about, long_about = None, subcommand_negates_reqs = true)]
 struct Args {
     /// File or dir to be converted
-    #[clap(required = true, parse(from_os_str), value_name = "FILE")]
+    #[clap(
+        required_unless_present = "manifest",
+        parse(from_os_str),
+        value_name = "FILE"
+    )]
     paths: Vec<PathBuf>,
 
+    /// Also convert the files or dirs listed in FILE: a json array of paths,
+    /// or toml with a `path` in each `[[files]]` table. Relative paths are
+    /// relative to the manifest
+    #[clap(long, parse(from_os_str), value_name = "FILE")]
+    manifest: Option<PathBuf>,
+
+    /// How many levels of directories to descend into. `0` only converts the
+    /// given paths that are files, and `1` the html files directly inside the
+    /// given directories. Unlimited by default
+    #[clap(long, value_name = "N")]
+    recursive_depth: Option<usize>,
+
+    /// Stop at the first file that fails to convert. With `--fail-fast=false`,
+    /// every file is tried and the failures are reported at the end
+    #[clap(
+        long,
+        parse(try_from_str),
+        default_value = "true",
+        default_missing_value = "true",
+        min_values = 0,
+        max_values = 1,
+        require_equals = true,
+        value_name = "BOOL"
+    )]
+    fail_fast: bool,
+
+    /// Index of the table to use when the page has no `groupMember` table,
+    /// counting from 0
+    #[clap(long, default_value_t = 0, value_name = "N")]
+    table_index: usize,
+
+    /// Comma separated list of columns to write, by header (`成员`) or english
+    /// name (`display_name`), as written with `--header-lang`. Defaults to
+    /// `成员,群昵称,QQ号,性别,入群时间`
+    #[clap(long, value_delimiter = ',', value_name = "COLUMNS")]
+    columns: Option<Vec<String>>,
+
+    /// Only keep members who have spoken within the last DAYS days
+    #[clap(long, value_name = "DAYS")]
+    filter_active_within: Option<u32>,
+
+    /// Only keep members who haven't spoken within the last DAYS days, or
+    /// never have: includes members inactive for at least DAYS days
+    #[clap(long, value_name = "DAYS")]
+    filter_inactive_since: Option<u64>,
+
+    /// Only keep members whose QQ name or group nickname contains PATTERN,
+    /// ignoring case
+    #[clap(long, value_name = "PATTERN")]
+    name_contains: Option<String>,
+
+    /// Only keep members whose QQ name or group nickname matches the regex
+    /// PATTERN
+    #[clap(long, value_name = "PATTERN")]
+    name_regex: Option<Regex>,
+
+    /// Only keep members whose QQ number is in RANGE, written as in rust:
+    /// `10000..100000` includes 10000 but not 100000, `10000..=100000`
+    /// includes both, and either end can be left out
+    #[clap(long, parse(try_from_str = parse_qq_number_range), value_name = "RANGE")]
+    qq_number_range: Option<Range<u64>>,
+
+    /// Only keep members who joined at least YEARS complete years ago
+    #[clap(long, value_name = "YEARS")]
+    filter_tenure_min: Option<u32>,
+
+    /// Only keep members who joined at most YEARS complete years ago
+    #[clap(long, value_name = "YEARS")]
+    filter_tenure_max: Option<u32>,
+
+    /// Skip the first N members, after filtering
+    #[clap(long, default_value_t = 0, value_name = "N")]
+    offset: usize,
+
+    /// Write at most N members, after filtering and --offset
+    #[clap(long, value_name = "N")]
+    limit: Option<usize>,
+
+    /// Add a column with a link to each member's QQ profile
+    #[clap(
+        long,
+        parse(try_from_str),
+        default_value = "false",
+        default_missing_value = "true",
+        min_values = 0,
+        max_values = 1,
+        require_equals = true,
+        value_name = "BOOL"
+    )]
+    include_profile_url: bool,
+
+    /// Add a column with the url of each member's avatar
+    #[clap(
+        long,
+        parse(try_from_str),
+        default_value = "false",
+        default_missing_value = "true",
+        min_values = 0,
+        max_values = 1,
+        require_equals = true,
+        value_name = "BOOL"
+    )]
+    include_avatar_url: bool,
+
+    /// Add a column with each member's role (owner, admin or member)
+    #[clap(
+        long,
+        parse(try_from_str),
+        default_value = "false",
+        default_missing_value = "true",
+        min_values = 0,
+        max_values = 1,
+        require_equals = true,
+        value_name = "BOOL"
+    )]
+    include_role: bool,
+
+    /// Add a `不活跃天数` column with the number of days since each member
+    /// last spoke, empty if unknown
+    #[clap(
+        long,
+        parse(try_from_str),
+        default_value = "false",
+        default_missing_value = "true",
+        min_values = 0,
+        max_values = 1,
+        require_equals = true,
+        value_name = "BOOL"
+    )]
+    include_days_inactive: bool,
+
+    /// Add a `序号` column first, with the row numbers of the member page
+    #[clap(
+        long,
+        parse(try_from_str),
+        default_value = "false",
+        default_missing_value = "true",
+        min_values = 0,
+        max_values = 1,
+        require_equals = true,
+        value_name = "BOOL"
+    )]
+    include_index: bool,
+
+    /// Number of files to convert in parallel. 0 uses one thread per CPU
+    #[clap(short, long, default_value_t = 1, value_name = "N")]
+    jobs: usize,
+
+    /// Add rows to the end of an existing csv instead of overwriting it. The
+    /// csv must have the same header as would be written
+    #[clap(
+        long,
+        parse(try_from_str),
+        default_value = "false",
+        default_missing_value = "true",
+        min_values = 0,
+        max_values = 1,
+        require_equals = true,
+        value_name = "BOOL"
+    )]
+    append: bool,
+
+    /// Skip html files whose output file already exists, instead of
+    /// overwriting it. With `--watch`, only new exports are converted
+    #[clap(
+        long,
+        parse(try_from_str),
+        default_value = "false",
+        default_missing_value = "true",
+        min_values = 0,
+        max_values = 1,
+        require_equals = true,
+        value_name = "BOOL"
+    )]
+    no_overwrite: bool,
+
+    /// Don't write the csv header row
+    #[clap(
+        long,
+        parse(try_from_str),
+        default_value = "false",
+        default_missing_value = "true",
+        min_values = 0,
+        max_values = 1,
+        require_equals = true,
+        value_name = "BOOL"
+    )]
+    no_header: bool,
+
+    /// Start the csv with a UTF-8 BOM, so that Excel detects the encoding
+    #[clap(
+        long,
+        parse(try_from_str),
+        default_value = "false",
+        default_missing_value = "true",
+        min_values = 0,
+        max_values = 1,
+        require_equals = true,
+        value_name = "BOOL"
+    )]
+    bom: bool,
+
+    /// Field delimiter of the csv, a single ASCII character or `tab`
+    #[clap(long, parse(try_from_str = parse_delimiter), default_value = ",", value_name = "CHAR")]
+    delimiter: u8,
+
+    /// Format of the output files
+    #[clap(long, arg_enum, default_value_t = Format::Csv)]
+    format: Format,
+
+    /// Replace QQ numbers and names with salted hashes, for sharing the data.
+    /// The salt is random for each run, and printed so that it can be kept
+    #[clap(
+        long,
+        parse(try_from_str),
+        default_value = "false",
+        default_missing_value = "true",
+        min_values = 0,
+        max_values = 1,
+        require_equals = true,
+        value_name = "BOOL"
+    )]
+    anonymize: bool,
+
+    /// Write a separate file for the members who joined in each year, named
+    /// like `<name>_2021.csv`, and `<name>_unknown.csv` for unknown join dates
+    #[clap(
+        long,
+        parse(try_from_str),
+        default_value = "false",
+        default_missing_value = "true",
+        min_values = 0,
+        max_values = 1,
+        require_equals = true,
+        value_name = "BOOL"
+    )]
+    split_by_year: bool,
+
+    /// Write a separate file for each gender, named like `<name>_male.csv`,
+    /// `<name>_female.csv` and `<name>_unknown_gender.csv`
+    #[clap(
+        long,
+        parse(try_from_str),
+        default_value = "false",
+        default_missing_value = "true",
+        min_values = 0,
+        max_values = 1,
+        require_equals = true,
+        value_name = "BOOL"
+    )]
+    split_by_gender: bool,
+
+    /// Language of the column headers
+    #[clap(long, arg_enum, default_value_t = HeaderLang::Zh, value_name = "LANG")]
+    header_lang: HeaderLang,
+
+    /// Write to FILE instead of next to each html file, or to stdout with `-`.
+    /// With several html files, use `--append` to keep the output of all of
+    /// them. Only works with a single job
+    #[clap(short, long, parse(from_os_str), value_name = "FILE")]
+    output: Option<PathBuf>,
+
+    /// Render each member with the Handlebars template in FILE instead of
+    /// writing a format. Fields are named as with `--header-lang en`, e.g.
+    /// `{{display_name}}`
+    #[clap(
+        long,
+        parse(from_os_str),
+        value_name = "FILE",
+        conflicts_with = "format"
+    )]
+    template: Option<PathBuf>,
+
+    /// Name of the table to create and insert into with `--format sql`
+    #[clap(long, default_value = "qq_members", value_name = "NAME")]
+    sql_table: String,
+
+    /// Add a `群名称` column before the others, with the group name from the
+    /// page title. Only for csv output
+    #[clap(
+        long,
+        parse(try_from_str),
+        default_value = "false",
+        default_missing_value = "true",
+        min_values = 0,
+        max_values = 1,
+        require_equals = true,
+        value_name = "BOOL"
+    )]
+    group_name_from_title: bool,
+
+    /// Add a `群号` column with the group number, found in the page's meta
+    /// tags, scripts or title. Only for csv output
+    #[clap(
+        long,
+        parse(try_from_str),
+        default_value = "false",
+        default_missing_value = "true",
+        min_values = 0,
+        max_values = 1,
+        require_equals = true,
+        value_name = "BOOL"
+    )]
+    include_group_id: bool,
+
+    /// Warn about members who share a QQ name, and exit with 1 if there are
+    /// any. The output is written as usual
+    #[clap(
+        long,
+        parse(try_from_str),
+        default_value = "false",
+        default_missing_value = "true",
+        min_values = 0,
+        max_values = 1,
+        require_equals = true,
+        value_name = "BOOL"
+    )]
+    unique_names: bool,
+
+    /// Only check the html files for values that don't look like the member
+    /// page, print them to stderr, and exit with 1 if there are any. Nothing
+    /// is written
+    #[clap(long)]
+    validate: bool,
+
+    /// Print a line like `group.html: 128 members → group.csv` to stderr for
+    /// each converted file
+    #[clap(
+        long,
+        parse(try_from_str),
+        default_value = "false",
+        default_missing_value = "true",
+        min_values = 0,
+        max_values = 1,
+        require_equals = true,
+        value_name = "BOOL"
+    )]
+    summary: bool,
+
+    /// Keep running, and convert html files again whenever they change
+    #[clap(
+        long,
+        parse(try_from_str),
+        default_value = "false",
+        default_missing_value = "true",
+        min_values = 0,
+        max_values = 1,
+        require_equals = true,
+        value_name = "BOOL"
+    )]
+    watch: bool,
+
+    /// Read default options from this toml file, instead of `./config.toml` or
+    /// `$XDG_CONFIG_HOME/qq-group-extract/config.toml`
+    #[clap(long, parse(from_os_str), value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    #[clap(subcommand)]
+    command: Option<Command>,
+
     #[clap(flatten)]
     verbose: clap_verbosity_flag::Verbosity,
 }
 
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print who joined, left or changed their group nickname between two html
+    /// exports of the same group. Exits with 0 if nothing changed, 1 if
+    /// something did, and 2 if an export can't be read
+    Diff {
+        /// The earlier export
+        #[clap(parse(from_os_str))]
+        before: PathBuf,
+
+        /// The later export
+        #[clap(parse(from_os_str))]
+        after: PathBuf,
+
+        /// Format of the report
+        #[clap(long, arg_enum, default_value_t = DiffFormat::Text)]
+        diff_format: DiffFormat,
+    },
+
+    /// Print statistics about the members of a group, without writing any
+    /// files
+    Stats {
+        /// The html export
+        #[clap(parse(from_os_str))]
+        path: PathBuf,
+
+        /// Format of the statistics
+        #[clap(long, arg_enum, default_value_t = StatsFormat::Text)]
+        format: StatsFormat,
+    },
+
+    /// Print the members who are in every one of several groups, by QQ
+    /// number
+    #[clap(long_flag = "cross-ref")]
+    CrossRef {
+        /// The html exports, one per group
+        #[clap(parse(from_os_str), required = true, min_values = 2)]
+        paths: Vec<PathBuf>,
+    },
+}
+
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum StatsFormat {
+    /// A short summary, for reading
+    Text,
+    /// A `GroupStats` object, for scripts
+    Json,
+}
+
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum DiffFormat {
+    /// One line per change, for reading
+    Text,
+    /// A `GroupDiff` object, for scripts
+    Json,
+}
+
+#[derive(ArgEnum, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum Format {
+    Csv,
+    /// `CREATE TABLE` and `INSERT` statements
+    Sql,
+    /// One JSON object per line
+    Ndjson,
+    /// OpenDocument spreadsheet, for LibreOffice
+    Ods,
+    /// Apache Parquet, for DuckDB, Spark and Pandas
+    Parquet,
+    /// Apache Arrow IPC file, for sharing with other processes
+    Arrow,
+    /// Aligned table for reading in a terminal, with `--output -`
+    Table,
+    /// LaTeX `tabular` environment
+    Latex,
+    /// HTML page with a table, written to `<name>.table.html`
+    #[serde(rename = "handlebars-html")]
+    HandlebarsHtml,
+}
+
+impl Format {
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Csv => "csv",
+            Format::Sql => "sql",
+            Format::Ndjson => "ndjson",
+            Format::Ods => "ods",
+            Format::Parquet => "parquet",
+            Format::Arrow => "arrow",
+            Format::Table => "txt",
+            Format::Latex => "tex",
+            Format::HandlebarsHtml => "table.html",
+        }
+    }
+}
+
+#[derive(ArgEnum, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum HeaderLang {
+    /// The headers of the member page, e.g. `成员`
+    Zh,
+    /// English headers, e.g. `display_name`
+    En,
+}
+
+impl HeaderLang {
+    fn column_names(self) -> ColumnNames {
+        match self {
+            HeaderLang::Zh => ColumnNames::ZH,
+            HeaderLang::En => ColumnNames::EN,
+        }
+    }
+}
+
 fn main() -> Result<()> {
-    let args = Args::parse();
-    pretty_env_logger::env_logger::Builder::new()
-        // .filter_level(args.verbose.log_level_filter())
-        .filter_module("qq_group_name_extract", args.verbose.log_level_filter())
+    let matches = Args::command().get_matches();
+    let mut args = Args::from_arg_matches(&matches)?;
+    // clap_verbosity_flag turns logging off entirely for `--quiet`, but errors
+    // should still be shown
+    let quiet = matches.is_present("quiet");
+    let log_level = if quiet {
+        log::LevelFilter::Error
+    } else {
+        args.verbose.log_level_filter()
+    };
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(io::stderr)
+                .with_ansi(io::stderr().is_terminal()),
+        )
+        .with(Targets::new().with_target("qq_group_name_extract", tracing_level(log_level)))
         .init();
 
-    let paths = args.paths;
+    if let Some(config) = Config::load(args.config.as_deref())? {
+        config.apply(&mut args, &matches)?;
+    }
+    check_conflicts(&args)?;
+
+    match &args.command {
+        Some(Command::Diff {
+            before,
+            after,
+            diff_format,
+        }) => {
+            let code = match print_diff(before, after, args.table_index, *diff_format) {
+                Ok(false) => 0,
+                Ok(true) => 1,
+                Err(e) => {
+                    eprintln!("Error: {e:?}");
+                    2
+                }
+            };
+            process::exit(code);
+        }
+        Some(Command::Stats { path, format }) => {
+            return print_stats(path, args.table_index, *format);
+        }
+        Some(Command::CrossRef { paths }) => {
+            return print_cross_ref(paths, args.table_index);
+        }
+        None => {}
+    }
+
+    let mut paths = args.paths;
+    if let Some(manifest) = &args.manifest {
+        paths.extend(manifest::load(manifest)?);
+    }
 
     info!("Given path: {:?}", paths);
 
+    let mut columns = match &args.columns {
+        Some(names) => select_columns(names)?,
+        None => (0..DEFAULT_COLUMNS).collect(),
+    };
+    if args.include_profile_url {
+        include_column(&mut columns, "profile_url");
+    }
+    if args.include_avatar_url {
+        include_column(&mut columns, "avatar_url");
+    }
+    if args.include_role {
+        include_column(&mut columns, "role");
+    }
+    if args.include_days_inactive {
+        include_column(&mut columns, "days_inactive");
+    }
+    if args.include_index {
+        // first, as on the member page
+        let i = column_position("index");
+        columns.retain(|&c| c != i);
+        columns.insert(0, i);
+    }
+
+    let options = ConvertOptions {
+        table_index: args.table_index,
+        columns,
+        filter: {
+            let mut filter = MemberFilter::new(Local::now().date_naive());
+            if let Some(days) = args.filter_active_within {
+                filter = filter.active_within(days);
+            }
+            if let Some(days) = args.filter_inactive_since {
+                filter = filter.inactive_since(days);
+            }
+            if let Some(s) = &args.name_contains {
+                filter = filter.name_contains(s);
+            }
+            if let Some(re) = &args.name_regex {
+                filter = filter.name_matches(re);
+            }
+            if let Some(range) = &args.qq_number_range {
+                filter = filter.qq_number_range(range.start, range.end);
+            }
+            if let Some(years) = args.filter_tenure_min {
+                filter = filter.tenure_min(years);
+            }
+            if let Some(years) = args.filter_tenure_max {
+                filter = filter.tenure_max(years);
+            }
+            filter
+        },
+        offset: args.offset,
+        limit: args.limit,
+        append: args.append,
+        no_overwrite: args.no_overwrite,
+        no_header: args.no_header,
+        bom: args.bom,
+        delimiter: args.delimiter,
+        anonymize_salt: args.anonymize.then(|| {
+            let salt = Uuid::new_v4().to_string();
+            // even with --quiet, since the members can't be re-identified
+            // without it
+            eprintln!("Anonymization salt: {salt}");
+            salt
+        }),
+        split_by: if args.split_by_year {
+            Some(SplitBy::Year)
+        } else if args.split_by_gender {
+            Some(SplitBy::Gender)
+        } else {
+            None
+        },
+        format: args.format,
+        header_lang: args.header_lang,
+        output: args.output,
+        sql_table: args.sql_table,
+        template: match &args.template {
+            Some(path) => Some(
+                fs::read_to_string(path)
+                    .wrap_err_with(|| format!("Failed to read template {path:?}"))?,
+            ),
+            None => None,
+        },
+        summary: args.summary,
+        group_name_from_title: args.group_name_from_title,
+        include_group_id: args.include_group_id,
+        unique_names: args.unique_names,
+        quiet,
+    };
+
+    let to_stdout = options.output.as_deref() == Some(Path::new(STDOUT));
+    match options.format {
+        Format::Ods if options.append => {
+            bail!("`--append` is not supported with `--format ods`")
+        }
+        Format::Ods if to_stdout => bail!("`--format ods` can't be written to stdout"),
+        Format::Parquet if options.append => {
+            bail!("`--append` is not supported with `--format parquet`")
+        }
+        Format::Parquet if to_stdout => bail!("`--format parquet` can't be written to stdout"),
+        Format::Arrow if options.append => {
+            bail!("`--append` is not supported with `--format arrow`")
+        }
+        Format::Table if !to_stdout => bail!("`--format table` needs `--output -`"),
+        _ => {}
+    }
+    let csv_only = [
+        ("--group-name-from-title", options.group_name_from_title),
+        ("--include-group-id", options.include_group_id),
+    ];
+    if options.format != Format::Csv || options.template.is_some() {
+        if let Some((flag, _)) = csv_only.iter().find(|(_, given)| *given) {
+            bail!("`{flag}` only applies to `--format csv`");
+        }
+    }
+    if options.split_by.is_some() && to_stdout {
+        bail!("`--split-by-year` and `--split-by-gender` can't be used with `--output -`");
+    }
+
+    let files: Vec<PathBuf> = paths
+        .iter()
+        .flat_map(|path| {
+            let mut walk = WalkDir::new(path);
+            if let Some(depth) = args.recursive_depth {
+                walk = walk.max_depth(depth);
+            }
+            walk.into_iter()
+                .filter_map(|e| e.ok())
+                .map(|e| e.path().to_owned())
+                .filter(|p| p.is_file() && is_html(p))
+        })
+        .collect();
+    if args.validate {
+        let found = validate_files(&files)?;
+        process::exit(found.into());
+    }
+    let (files, skipped) = skip_converted(files, &options);
+
+    let progress = progress_bar(files.len(), quiet);
+    let members_written = AtomicUsize::new(0);
+    let duplicate_names = AtomicBool::new(false);
+
+    // Ok(Some(e)) is a failure that --fail-fast=false lets us carry on from
+    let convert = |path: &PathBuf| -> Result<Option<ConvertError>, ConvertError> {
+        progress.inc(1);
+        progress.set_message(path.display().to_string());
+        match convert_html(path, &options) {
+            Ok(converted) => {
+                members_written.fetch_add(converted.members, Ordering::Relaxed);
+                if converted.duplicate_names {
+                    duplicate_names.store(true, Ordering::Relaxed);
+                }
+                Ok(None)
+            }
+            Err(e) if !args.fail_fast => {
+                error!("{}", display_chain(&e));
+                Ok(Some(e))
+            }
+            Err(e) => Err(e),
+        }
+    };
+
+    let results = if args.jobs == 1 {
+        files.iter().map(convert).collect::<Result<Vec<_>, _>>()?
+    } else {
+        ThreadPoolBuilder::new()
+            .num_threads(args.jobs)
+            .build()
+            .wrap_err("Failed to start worker threads")?
+            .install(|| files.par_iter().map(convert).collect::<Result<Vec<_>, _>>())?
+    };
+    let failed: Vec<_> = results.into_iter().flatten().map(|e| e.path).collect();
+    progress.finish_and_clear();
+    // a single file has nothing to add up
+    if !quiet && files.len() > 1 {
+        eprintln!(
+            "Processed {}, {} total",
+            plural(files.len() - failed.len(), "file"),
+            plural(members_written.into_inner(), "member")
+        );
+    }
+    log_skipped(skipped);
+
+    if !failed.is_empty() {
+        bail!("Failed to convert {} file(s): {failed:?}", failed.len());
+    }
+
+    if args.watch {
+        return watch(&paths, args.recursive_depth, &options);
+    }
+
+    if duplicate_names.into_inner() {
+        process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// Fails if two flags that can't be used together are both on, whether from
+/// the command line or the config file
+fn check_conflicts(args: &Args) -> Result<()> {
+    let conflicts = [
+        ("--no-overwrite", args.no_overwrite, "--append", args.append),
+        (
+            "--no-overwrite",
+            args.no_overwrite,
+            "--split-by-year",
+            args.split_by_year,
+        ),
+        (
+            "--no-overwrite",
+            args.no_overwrite,
+            "--split-by-gender",
+            args.split_by_gender,
+        ),
+        ("--no-header", args.no_header, "--append", args.append),
+        (
+            "--anonymize",
+            args.anonymize,
+            "--include-profile-url",
+            args.include_profile_url,
+        ),
+        (
+            "--anonymize",
+            args.anonymize,
+            "--include-avatar-url",
+            args.include_avatar_url,
+        ),
+        (
+            "--split-by-gender",
+            args.split_by_gender,
+            "--split-by-year",
+            args.split_by_year,
+        ),
+    ];
+    for (flag, on, other, other_on) in conflicts {
+        if on && other_on {
+            bail!("`{flag}` can't be used with `{other}`");
+        }
+    }
+    // parallel jobs would interleave their writes to the one file, or stdout
+    if args.output.is_some() && args.jobs != 1 {
+        bail!("`--output` can't be used with `--jobs` other than 1");
+    }
+    Ok(())
+}
+
+/// The `tracing` equivalent of a `log` level, which is what
+/// clap_verbosity_flag gives
+fn tracing_level(level: log::LevelFilter) -> LevelFilter {
+    match level {
+        log::LevelFilter::Off => LevelFilter::OFF,
+        log::LevelFilter::Error => LevelFilter::ERROR,
+        log::LevelFilter::Warn => LevelFilter::WARN,
+        log::LevelFilter::Info => LevelFilter::INFO,
+        log::LevelFilter::Debug => LevelFilter::DEBUG,
+        log::LevelFilter::Trace => LevelFilter::TRACE,
+    }
+}
+
+/// `count` followed by `noun`, with an `s` unless `count` is 1
+fn plural(count: usize, noun: &str) -> String {
+    let s = if count == 1 { "" } else { "s" };
+    format!("{count} {noun}{s}")
+}
+
+/// A bar counting through `len` files on stderr. It stays hidden for a single
+/// file, with `--quiet`, and when stderr isn't a terminal
+fn progress_bar(len: usize, quiet: bool) -> ProgressBar {
+    if len <= 1 || quiet || !io::stderr().is_terminal() {
+        return ProgressBar::hidden();
+    }
+    let style = ProgressStyle::with_template("Processing file {pos}/{len} {bar:30} {wide_msg}")
+        .expect("progress bar template should be valid");
+    ProgressBar::new(len as u64).with_style(style)
+}
+
+fn is_html(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "html")
+}
+
+/// Converts html files under `paths` again whenever they are created or
+/// modified, until the process is killed. With a `depth` of 1 or less, only
+/// the files directly inside `paths` are watched
+fn watch(paths: &[PathBuf], depth: Option<usize>, options: &ConvertOptions) -> Result<()> {
+    let (tx, rx) = mpsc::channel();
+    // editors often write a file several times when saving it
+    let mut debouncer = new_debouncer(std::time::Duration::from_millis(500), tx)
+        .wrap_err("Failed to create file watcher")?;
+    let mode = match depth {
+        Some(depth) if depth <= 1 => RecursiveMode::NonRecursive,
+        _ => RecursiveMode::Recursive,
+    };
     for path in paths {
-        for path in WalkDir::new(path)
+        debouncer
+            .watcher()
+            .watch(path, mode)
+            .wrap_err_with(|| format!("Failed to watch {path:?}"))?;
+    }
+
+    info!("Watching for changes, press Ctrl-C to stop");
+    for result in rx {
+        let events = match result {
+            Ok(events) => events,
+            Err(e) => {
+                error!("Error while watching files: {e:?}");
+                continue;
+            }
+        };
+        let changed = events
             .into_iter()
-            .filter_map(|e| e.ok())
-            .map(|e| e.path().to_owned())
-            .filter(|p| p.is_file() && p.extension().unwrap() == "html")
-        {
-            convert_html(&path)
-                .wrap_err_with(|| format!("Error while converting to html: {path:?}"))?;
+            .map(|event| event.path)
+            .filter(|path| path.is_file() && is_html(path))
+            .collect();
+        let (changed, skipped) = skip_converted(changed, options);
+        for path in changed {
+            if let Err(e) = convert_html(&path, options) {
+                error!("{}", display_chain(&e));
+            }
         }
+        log_skipped(skipped);
     }
 
     Ok(())
 }
 
-fn convert_html<T: AsRef<Path>>(path: T) -> Result<()> {
-    let path = path.as_ref();
+/// With `--no-overwrite`, leaves out the `files` whose output already exists.
+/// Returns the files left, and how many were left out
+fn skip_converted(files: Vec<PathBuf>, options: &ConvertOptions) -> (Vec<PathBuf>, usize) {
+    if !options.no_overwrite {
+        return (files, 0);
+    }
+    let count = files.len();
+    let files: Vec<_> = files
+        .into_iter()
+        .filter(|path| {
+            let out_path = out_path(path, options);
+            let exists = out_path != Path::new(STDOUT) && out_path.exists();
+            if exists {
+                debug!("Skipping {path:?}, {out_path:?} already exists");
+            }
+            !exists
+        })
+        .collect();
+    let skipped = count - files.len();
+    (files, skipped)
+}
+
+fn log_skipped(skipped: usize) {
+    if skipped > 0 {
+        info!("Skipped {skipped} file(s) that were already converted");
+    }
+}
+
+/// Columns of the csv output, as `(header, english name)`. The columns of
+/// [`Member::csv_header`] take their names from [`ColumnNames`]. Only the first
+/// [`DEFAULT_COLUMNS`] are written unless others are asked for
+const COLUMNS: [(&str, &str); 12] = [
+    (ColumnNames::ZH.qq_name, ColumnNames::EN.qq_name),
+    (ColumnNames::ZH.group_name, ColumnNames::EN.group_name),
+    (ColumnNames::ZH.qq_number, ColumnNames::EN.qq_number),
+    (ColumnNames::ZH.gender, ColumnNames::EN.gender),
+    (ColumnNames::ZH.joined_date, ColumnNames::EN.joined_date),
+    (ColumnNames::ZH.qq_age, ColumnNames::EN.qq_age),
+    (
+        ColumnNames::ZH.last_spoken_date,
+        ColumnNames::EN.last_spoken_date,
+    ),
+    ("主页链接", "profile_url"),
+    ("头像链接", "avatar_url"),
+    ("身份", "role"),
+    ("序号", "index"),
+    ("不活跃天数", "days_inactive"),
+];
+
+const DEFAULT_COLUMNS: usize = 5;
+
+/// Header of the column at index `column` of [`COLUMNS`] in `lang`
+fn column_header(column: usize, lang: HeaderLang) -> &'static str {
+    let (header, name) = COLUMNS[column];
+    match lang {
+        HeaderLang::Zh => header,
+        HeaderLang::En => name,
+    }
+}
+
+/// Value of the column at index `column` of [`COLUMNS`] for `member`, whose
+/// [`Member::to_csv_record`] is `record`
+///
+/// Columns in [`Member::csv_header`] are taken from `record`, so that they are
+/// formatted the same way everywhere.
+fn column_value(member: &Member, record: &[String], column: usize) -> String {
+    let (header, name) = COLUMNS[column];
+    if let Some(i) = Member::csv_header().iter().position(|&h| h == header) {
+        return record[i].clone();
+    }
+    match name {
+        "profile_url" => member.profile_url(),
+        "avatar_url" => member.avatar_url.clone().unwrap_or_default(),
+        "role" => member.role.to_string(),
+        "index" => member.index.map(|i| i.to_string()).unwrap_or_default(),
+        "days_inactive" => member
+            .days_since_last_spoken(Local::now().date_naive())
+            .map(|days| days.to_string())
+            .unwrap_or_default(),
+        name => unreachable!("column `{name}` has no value"),
+    }
+}
+
+/// Resolves column names given on the command line to indices into [`COLUMNS`]
+fn select_columns(names: &[String]) -> Result<Vec<usize>> {
+    names
+        .iter()
+        .map(|name| {
+            let name = name.trim();
+            COLUMNS
+                .iter()
+                .position(|&(header, en)| name == header || name.eq_ignore_ascii_case(en))
+                .ok_or_else(|| {
+                    let valid: Vec<_> = COLUMNS
+                        .iter()
+                        .map(|(header, en)| format!("{header} ({en})"))
+                        .collect();
+                    eyre!(
+                        "Unknown column `{name}`, valid columns are: {}",
+                        valid.join(", ")
+                    )
+                })
+        })
+        .collect()
+}
+
+/// Adds the column with english name `name` to `columns`, unless it's already
+/// there
+fn include_column(columns: &mut Vec<usize>, name: &str) {
+    let i = column_position(name);
+    if !columns.contains(&i) {
+        columns.push(i);
+    }
+}
+
+/// Index into [`COLUMNS`] of the column with english name `name`
+fn column_position(name: &str) -> usize {
+    COLUMNS
+        .iter()
+        .position(|&(_, en)| en == name)
+        .expect("name should be in COLUMNS")
+}
+
+/// Options for converting a single file, taken from [`Args`]
+struct ConvertOptions {
+    table_index: usize,
+    /// Indices into [`COLUMNS`] of the columns to write
+    columns: Vec<usize>,
+    /// Only members matching this are written
+    filter: MemberFilter,
+    offset: usize,
+    limit: Option<usize>,
+    /// Add rows to an existing csv instead of overwriting it
+    append: bool,
+    /// Skip files whose output already exists
+    no_overwrite: bool,
+    no_header: bool,
+    /// Start new files with [`UTF8_BOM`]
+    bom: bool,
+    delimiter: u8,
+    /// Anonymize members with this salt, see [`Member::anonymize`]
+    anonymize_salt: Option<String>,
+    /// Write a separate file for each group of members
+    split_by: Option<SplitBy>,
+    format: Format,
+    header_lang: HeaderLang,
+    /// Where to write instead of next to the html file, or [`STDOUT`]
+    output: Option<PathBuf>,
+    sql_table: String,
+    /// Handlebars template to render each member with, instead of `format`
+    template: Option<String>,
+    /// Print a line for each converted file
+    summary: bool,
+    /// Add the group name from the page title as the first csv column
+    group_name_from_title: bool,
+    /// Add the group number as a csv column, after the group name
+    include_group_id: bool,
+    /// Warn about members who share a QQ name
+    unique_names: bool,
+    /// Print nothing but errors and warnings to stderr
+    quiet: bool,
+}
+
+/// What [`convert_file`] did with one html file
+#[derive(Clone, Copy, Debug)]
+struct Converted {
+    /// The number of members written
+    members: usize,
+    /// Whether `--unique-names` found members sharing a name
+    duplicate_names: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum SplitBy {
+    Year,
+    Gender,
+}
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// A file that failed to convert
+#[derive(Debug)]
+struct ConvertError {
+    path: PathBuf,
+    /// Why it failed, either while reading the file or while writing its
+    /// output
+    source: QqExtractError,
+}
+
+impl fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Error while converting to html: {:?}", self.path)
+    }
+}
+
+impl std::error::Error for ConvertError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+#[instrument(skip(options))]
+fn convert_html(path: &Path, options: &ConvertOptions) -> Result<Converted, ConvertError> {
+    convert_file(path, options).map_err(|source| ConvertError {
+        path: path.to_owned(),
+        source,
+    })
+}
 
+/// Converts the html file at `path`
+fn convert_file(path: &Path, options: &ConvertOptions) -> Result<Converted, QqExtractError> {
     info!("Converting path: {path:?}");
 
-    let mut file_str = String::new();
-    File::open(path)
-        .wrap_err_with(|| format!("Failed to open file {path:?}"))?
-        .read_to_string(&mut file_str)
-        .wrap_err_with(|| format!("Failed to read file {path:?}"))?;
+    let export = GroupExport::from_file_or_nth(path, options.table_index)?;
+    debug!("Group id: {:?}", export.group_id);
+    let group_columns = group_columns(&export, options);
+    let duplicate_names = options.unique_names
+        && warn_duplicate_names(path, &export, options.anonymize_salt.as_deref());
+    let mut table = export.members;
+
+    table.retain(|member| options.filter.matches(member));
+    if let Some(cutoff) = options.filter.inactive_cutoff() {
+        info!(
+            "{} members in {path:?} haven't spoken since before {cutoff}",
+            table.len()
+        );
+    }
+
+    let mut table: Vec<_> = table
+        .into_iter()
+        .skip(options.offset)
+        .take(options.limit.unwrap_or(usize::MAX))
+        .collect();
+    if table.is_empty() && (options.offset > 0 || options.limit.is_some()) {
+        warn!("No members left to write for {path:?} after --offset and --limit");
+    }
+
+    if let Some(salt) = &options.anonymize_salt {
+        for member in &mut table {
+            member.anonymize(salt);
+        }
+    }
+
+    let count = table.len();
+    let out_path = out_path(path, options);
+
+    if let Some(split_by) = options.split_by {
+        let mut groups: Vec<(String, Vec<Member>)> = match split_by {
+            SplitBy::Year => split_members(table, |member| member.joined_date.map(|d| d.year()))
+                .into_iter()
+                .map(|(year, members)| {
+                    let suffix = year.map_or_else(|| "unknown".to_owned(), |y| y.to_string());
+                    (suffix, members)
+                })
+                .collect(),
+            SplitBy::Gender => split_members(table, |member| member.gender.clone())
+                .into_iter()
+                .map(|(gender, members)| {
+                    let suffix = match gender {
+                        Gender::Male => "male",
+                        Gender::Female => "female",
+                        Gender::Unknown => "unknown_gender",
+                    };
+                    (suffix.to_owned(), members)
+                })
+                .collect(),
+        };
+        groups.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut summary = Vec::new();
+        let mut split_paths = Vec::new();
+        for (suffix, members) in groups {
+            let split_path = split_path(&out_path, &suffix);
+            write_members(&split_path, &members, &group_columns, options).map_err(|source| {
+                QqExtractError::Write {
+                    path: split_path.clone(),
+                    source,
+                }
+            })?;
+            summary.push(format!("{} ({})", split_path.display(), members.len()));
+            split_paths.push(split_path.display().to_string());
+        }
+        if !options.quiet {
+            eprintln!("Split {path:?} into {}", summary.join(", "));
+        }
+        if options.summary {
+            print_summary(path, count, &split_paths.join(", "));
+        }
+        return Ok(Converted {
+            members: count,
+            duplicate_names,
+        });
+    }
+
+    write_members(&out_path, &table, &group_columns, options).map_err(|source| {
+        QqExtractError::Write {
+            path: out_path.clone(),
+            source,
+        }
+    })?;
+    if options.summary {
+        let out_path = if out_path == Path::new(STDOUT) {
+            Cow::Borrowed("<stdout>")
+        } else {
+            out_path.to_string_lossy()
+        };
+        print_summary(path, count, &out_path);
+    }
+    Ok(Converted {
+        members: count,
+        duplicate_names,
+    })
+}
+
+/// Warns about each QQ name in `export` that more than one member has, for
+/// `--unique-names`. Returns whether there were any
+///
+/// With `--anonymize`, the names are left out and the QQ numbers are hashed
+/// with `salt`, as they are in the output
+fn warn_duplicate_names(path: &Path, export: &GroupExport, salt: Option<&str>) -> bool {
+    let duplicates = export.duplicate_names();
+    for (name, members) in &duplicates {
+        let numbers: Vec<_> = members
+            .iter()
+            .map(|&m| match salt {
+                Some(salt) => {
+                    let mut member = m.clone();
+                    member.anonymize(salt);
+                    member.qq_number
+                }
+                None => m.qq_number.clone(),
+            })
+            .collect();
+        // printed rather than logged, since warnings are hidden by default
+        if salt.is_some() {
+            eprintln!(
+                "Warning: {} members in {path:?} share a name, with anonymized QQ numbers {}",
+                members.len(),
+                numbers.join(", ")
+            );
+        } else {
+            eprintln!(
+                "Warning: {} members in {path:?} are named `{name}`, with QQ numbers {}",
+                members.len(),
+                numbers.join(", ")
+            );
+        }
+    }
+    !duplicates.is_empty()
+}
+
+/// Prints the `--summary` line for the html file at `path` to stderr
+fn print_summary(path: &Path, count: usize, out: &str) {
+    eprintln!("{}: {count} members → {out}", path.display());
+}
+
+/// The csv columns that are the same for every member of `export`, as
+/// `(header, value)`
+fn group_columns(export: &GroupExport, options: &ConvertOptions) -> Vec<(&'static str, String)> {
+    let mut columns = Vec::new();
+    if options.group_name_from_title {
+        let header = match options.header_lang {
+            HeaderLang::Zh => "群名称",
+            HeaderLang::En => "group_title",
+        };
+        columns.push((header, export.group_name.clone().unwrap_or_default()));
+    }
+    if options.include_group_id {
+        let header = match options.header_lang {
+            HeaderLang::Zh => "群号",
+            HeaderLang::En => "group_id",
+        };
+        columns.push((header, export.group_id.clone().unwrap_or_default()));
+    }
+    columns
+}
+
+/// Where the output for the html file at `path` goes, before any splitting
+fn out_path(path: &Path, options: &ConvertOptions) -> PathBuf {
+    match &options.output {
+        Some(output) => output.clone(),
+        None if options.template.is_some() => path.with_extension("txt"),
+        None => path.with_extension(options.format.extension()),
+    }
+}
+
+/// Groups `members` by the key `key_fn` gives each of them
+fn split_members<K, F>(members: Vec<Member>, key_fn: F) -> HashMap<K, Vec<Member>>
+where
+    K: Eq + Hash,
+    F: Fn(&Member) -> K,
+{
+    let mut groups: HashMap<K, Vec<Member>> = HashMap::new();
+    for member in members {
+        groups.entry(key_fn(&member)).or_default().push(member);
+    }
+    groups
+}
+
+/// `out_path` with `_suffix` added to the file name, before the extension
+fn split_path(out_path: &Path, suffix: &str) -> PathBuf {
+    let mut file_name = out_path.file_stem().unwrap_or_default().to_owned();
+    file_name.push(format!("_{suffix}"));
+    if let Some(extension) = out_path.extension() {
+        file_name.push(".");
+        file_name.push(extension);
+    }
+    out_path.with_file_name(file_name)
+}
+
+/// Writes `table` to `out_path` in the format of `options`
+fn write_members(
+    out_path: &Path,
+    table: &[Member],
+    group_columns: &[(&'static str, String)],
+    options: &ConvertOptions,
+) -> Result<(), OutputError> {
+    if let Some(template) = &options.template {
+        return write_file(out_path, options.append, |w| {
+            write_template(table, template, w)
+        });
+    }
+    match options.format {
+        Format::Csv => write_csv(out_path, table, group_columns, options),
+        Format::Sql => write_file(out_path, options.append, |w| {
+            write_sql(table, &options.sql_table, w)
+        }),
+        Format::Ndjson => write_file(out_path, options.append, |w| write_ndjson(table, w)),
+        Format::Ods => {
+            if out_path.is_file() {
+                warn!("Overwriting file {out_path:?}");
+            }
+            write_ods(table, &options.header_lang.column_names(), out_path)
+        }
+        Format::Parquet => {
+            if out_path.is_file() {
+                warn!("Overwriting file {out_path:?}");
+            }
+            write_parquet(table, out_path)
+        }
+        Format::Arrow => write_file(out_path, options.append, |w| write_arrow_ipc(table, w)),
+        Format::Table => write_file(out_path, options.append, |w| {
+            write_terminal_table(table, &options.header_lang.column_names(), w)
+        }),
+        Format::Latex => write_file(out_path, options.append, |w| {
+            write_latex(table, &options.header_lang.column_names(), w)
+        }),
+        Format::HandlebarsHtml => write_file(out_path, options.append, |w| {
+            write_html_table(table, &options.header_lang.column_names(), w)
+        }),
+    }
+}
+
+/// Writes `table` as csv, each row starting with the values of
+/// `group_columns`
+fn write_csv(
+    out_path: &Path,
+    table: &[Member],
+    group_columns: &[(&'static str, String)],
+    options: &ConvertOptions,
+) -> Result<(), OutputError> {
+    let header: Vec<_> = group_columns
+        .iter()
+        .map(|&(header, _)| header)
+        .chain(
+            options
+                .columns
+                .iter()
+                .map(|&i| column_header(i, options.header_lang)),
+        )
+        .collect();
+
+    let append = options.append && out_path.is_file();
+    if append {
+        check_csv_header(out_path, &header, options.delimiter)?;
+    }
+    let mut file = open_output(out_path, append)?;
+    if !append && options.bom {
+        file.write_all(UTF8_BOM)?;
+    }
+
+    let mut wtr = WriterBuilder::new()
+        .delimiter(options.delimiter)
+        .from_writer(file);
+    // let writer = BufWriter::new(File::create(out_path)?);
+
+    if !append && !options.no_header {
+        wtr.write_record(&header)?;
+    }
+
+    for member in table.iter() {
+        let record = member.to_csv_record();
+        wtr.write_record(
+            group_columns.iter().map(|(_, value)| value.clone()).chain(
+                options
+                    .columns
+                    .iter()
+                    .map(|&i| column_value(member, &record, i)),
+            ),
+        )?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Fills `out_path` with `write`, for the formats other than csv. With
+/// `--append`, the output is added to the end of an existing file instead; none
+/// of these formats has a header that would be repeated (SQL only creates its
+/// table if it doesn't exist yet).
+fn write_file(
+    out_path: &Path,
+    append: bool,
+    write: impl FnOnce(&mut BufWriter<Box<dyn Write>>) -> Result<(), OutputError>,
+) -> Result<(), OutputError> {
+    let mut writer = BufWriter::new(open_output(out_path, append)?);
+    write(&mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// `--output` value for writing to stdout
+const STDOUT: &str = "-";
 
-    let table = Member::from_html(&file_str)
-        .wrap_err_with(|| format!("Error while parsing file {path:?}"))?;
+/// Opens `out_path` for writing, or stdout if it is [`STDOUT`]. With `append`,
+/// writes go to the end of the file instead of replacing it.
+fn open_output(out_path: &Path, append: bool) -> io::Result<Box<dyn Write>> {
+    if out_path == Path::new(STDOUT) {
+        return Ok(Box::new(io::stdout().lock()));
+    }
+    if append {
+        return Ok(Box::new(
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(out_path)?,
+        ));
+    }
 
-    let out_path = path.with_extension("csv");
     if out_path.is_file() {
         warn!("Overwriting file {out_path:?}");
     }
+    Ok(Box::new(File::create(out_path)?))
+}
 
-    let mut wtr = Writer::from_path(&out_path)
-        .wrap_err_with(|| format!("Failed to create csv writer for file {out_path:?}"))?;
-    // let writer = BufWriter::new(File::create(out_path)?);
+fn parse_delimiter(s: &str) -> Result<u8, String> {
+    if s == "tab" {
+        return Ok(b'\t');
+    }
+    match s.as_bytes() {
+        [b] if b.is_ascii() => Ok(*b),
+        _ => Err(format!("`{s}` is not a single ASCII character or `tab`")),
+    }
+}
+
+/// Parses a range of QQ numbers like `a..b`, `a..=b`, `a..` or `..b`. An
+/// inclusive end of `u64::MAX` is left out, as no QQ number is that large.
+fn parse_qq_number_range(s: &str) -> Result<Range<u64>, String> {
+    let (start, end) = s
+        .split_once("..")
+        .ok_or_else(|| format!("`{s}` is not a range like `10000..100000`"))?;
+    let number = |n: &str| {
+        n.trim()
+            .parse::<u64>()
+            .map_err(|e| format!("`{n}` in `{s}` is not a QQ number: {e}"))
+    };
+    let start = match start.trim() {
+        "" => 0,
+        start => number(start)?,
+    };
+    let end = match end.strip_prefix('=') {
+        Some(end) => number(end)?.saturating_add(1),
+        None if end.trim().is_empty() => u64::MAX,
+        None => number(end)?,
+    };
+    Ok(start..end)
+}
+
+/// Checks that the csv at `path` has the header `header`, so that rows can be
+/// appended to it
+fn check_csv_header(path: &Path, header: &[&str], delimiter: u8) -> Result<(), OutputError> {
+    let existing = ReaderBuilder::new()
+        .delimiter(delimiter)
+        .from_path(path)?
+        .headers()?
+        .clone();
+    if existing.iter().ne(header.iter().copied()) {
+        return Err(OutputError::HeaderMismatch {
+            existing: existing.iter().map(str::to_owned).collect(),
+            expected: header.iter().map(|&h| h.to_owned()).collect(),
+        });
+    }
+    Ok(())
+}
+
+/// Checks the member tables of `files` with [`Member::validate_html`], and
+/// prints what is wrong to stderr. Returns whether anything was
+fn validate_files(files: &[PathBuf]) -> Result<bool> {
+    let mut table = comfy_table::Table::new();
+    table
+        .load_preset(comfy_table::presets::UTF8_FULL)
+        .set_header(["File", "Row", "Field", "Value", "Problem"]);
+    for path in files {
+        let html = read_html_file(path)?;
+        let violations = Member::validate_html(&html)
+            .wrap_err_with(|| format!("Error while validating file {path:?}"))?;
+        for violation in violations {
+            table.add_row([
+                path.display().to_string(),
+                violation.row.to_string(),
+                violation.field.to_owned(),
+                violation.value,
+                violation.problem.to_owned(),
+            ]);
+        }
+    }
+
+    if table.row_count() == 0 {
+        eprintln!("No problems found in {} file(s)", files.len());
+        return Ok(false);
+    }
+    eprintln!("{table}");
+    eprintln!("Found {} problem(s), rows count from 0", table.row_count());
+    Ok(true)
+}
+
+/// Prints the changes between two exports to stdout. Returns whether there
+/// were any
+fn print_diff(before: &Path, after: &Path, table_index: usize, format: DiffFormat) -> Result<bool> {
+    let before = GroupExport::from_file_or_nth(before, table_index)?;
+    let after = GroupExport::from_file_or_nth(after, table_index)?;
+    let diff = GroupExport::diff(&before, &after);
+
+    match format {
+        DiffFormat::Text => {
+            for member in &diff.joined {
+                println!("+ {:<12} {}", member.qq_number, member.qq_name);
+            }
+            for member in &diff.left {
+                println!("- {:<12} {}", member.qq_number, member.qq_name);
+            }
+            for (before, after) in &diff.renamed {
+                println!(
+                    "~ {:<12} {}: {:?} -> {:?}",
+                    after.qq_number, after.qq_name, before.group_name, after.group_name
+                );
+            }
+            println!(
+                "{} joined, {} left, {} renamed",
+                diff.joined.len(),
+                diff.left.len(),
+                diff.renamed.len()
+            );
+        }
+        DiffFormat::Json => {
+            serde_json::to_writer_pretty(io::stdout().lock(), &diff)
+                .wrap_err("Failed to write diff")?;
+            println!();
+        }
+    }
+    Ok(!diff.is_empty())
+}
+
+/// Prints the statistics of an export to stdout
+fn print_stats(path: &Path, table_index: usize, format: StatsFormat) -> Result<()> {
+    let export = GroupExport::from_file_or_nth(path, table_index)?;
+    let stats = export.stats();
 
-    wtr.write_record(&[
-        // "id",
-        "成员",
-        "群昵称",
-        "QQ号",
-        "性别",
-        // "Q龄",
-        "入群时间",
-    ])
-    .wrap_err("Failed to write csv header")?;
-
-    for (_i, member) in table.iter().enumerate() {
-        wtr.write_record(&[
-            // &i.to_string(),
-            &member.qq_name,
-            &member.group_name,
-            &member.qq_name,
-            &member.gender.to_string(),
-            // &member.qq_age.to_string(),
-            &member.joined_date.to_string(),
-        ])
-        .wrap_err_with(|| format!("Filed to write record {member:?}"))?;
-    }
-    wtr.flush()
-        .wrap_err_with(|| format!("Failed to flush csv writer for {out_path:?}"))?;
+    match format {
+        StatsFormat::Text => {
+            match &export.group_id {
+                Some(id) => println!("Group {id}: {} members", stats.member_count),
+                None => println!("{} members", stats.member_count),
+            }
+            println!(
+                "Gender: {} male, {} female, {} unknown",
+                stats.male_count, stats.female_count, stats.unknown_gender_count
+            );
+            println!("Owner and admins: {}", stats.admin_count);
+            println!(
+                "Joined: {} to {}, {} unknown",
+                format_qq_date(stats.first_joined),
+                format_qq_date(stats.last_joined),
+                stats.unknown_joined_count
+            );
+            for (year, count) in &stats.joined_per_year {
+                println!("  {year}: {count}");
+            }
+            println!("Last spoken: {}", format_qq_date(stats.last_spoken));
+        }
+        StatsFormat::Json => {
+            serde_json::to_writer_pretty(io::stdout().lock(), &stats)
+                .wrap_err("Failed to write stats")?;
+            println!();
+        }
+    }
+    Ok(())
+}
+
+/// Prints the members who are in all of the exports at `paths` to stdout
+fn print_cross_ref(paths: &[PathBuf], table_index: usize) -> Result<()> {
+    let groups = paths
+        .iter()
+        .map(|path| GroupExport::from_file_or_nth(path, table_index))
+        .collect::<Result<Vec<_>, _>>()?;
+    let shared = GroupExport::cross_reference(&groups);
+
+    for member in &shared {
+        println!("{:<12} {}", member.qq_number, member.qq_name);
+    }
+    println!("{} members in all {} groups", shared.len(), groups.len());
     Ok(())
 }
@@ -0,0 +1,59 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use eyre::{bail, Context, Result};
+use serde::Deserialize;
+use tracing::info;
+
+/// A toml manifest, with one `[[files]]` table for each input
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TomlManifest {
+    files: Vec<TomlEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TomlEntry {
+    path: PathBuf,
+}
+
+/// Reads the input paths listed in the manifest at `path`: a json array of
+/// path strings if it ends in `.json`, and otherwise toml with a `path` in
+/// each `[[files]]` table. Relative paths are resolved against the directory
+/// of the manifest, and each must exist.
+pub fn load(path: &Path) -> Result<Vec<PathBuf>> {
+    info!("Reading manifest {path:?}");
+    let text =
+        fs::read_to_string(path).wrap_err_with(|| format!("Failed to read manifest {path:?}"))?;
+    let entries = if path.extension().is_some_and(|ext| ext == "json") {
+        serde_json::from_str(&text)
+            .wrap_err_with(|| format!("Failed to parse manifest {path:?}"))?
+    } else {
+        let manifest: TomlManifest =
+            toml::from_str(&text).wrap_err_with(|| format!("Failed to parse manifest {path:?}"))?;
+        manifest.files.into_iter().map(|entry| entry.path).collect()
+    };
+
+    let dir = path.parent().unwrap_or(Path::new(""));
+    resolve(dir, entries, path)
+}
+
+fn resolve(dir: &Path, entries: Vec<PathBuf>, manifest: &Path) -> Result<Vec<PathBuf>> {
+    entries
+        .into_iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let resolved = dir.join(&entry);
+            if !resolved.exists() {
+                bail!(
+                    "Entry {} of manifest {manifest:?}, {entry:?}, does not exist at {resolved:?}",
+                    i + 1
+                );
+            }
+            Ok(resolved)
+        })
+        .collect()
+}
@@ -0,0 +1,90 @@
+//! Arrow IPC file output, for sharing members with other processes such as
+//! Python analytics without parsing them again.
+
+use std::io::Write;
+
+use arrow2::{
+    array::{Array, Int32Array, Int64Array, Utf8Array},
+    chunk::Chunk,
+    datatypes::{DataType, Field, Schema},
+    io::ipc::write::{FileWriter, WriteOptions},
+};
+use chrono::NaiveDate;
+
+use super::days_since_epoch;
+use crate::{error::OutputError, qqtable::Member};
+
+/// Writes `members` to `writer` as an Arrow IPC file with a single record
+/// batch, which has a column for each field of [`Member`].
+///
+/// The columns are typed as in [`write_parquet`](super::parquet::write_parquet):
+/// dates are `Date32` and `qq_age` is the number of years.
+pub fn write_arrow_ipc(members: &[Member], writer: &mut impl Write) -> Result<(), OutputError> {
+    let strings = |field: fn(&Member) -> String| {
+        Utf8Array::<i32>::from_iter_values(members.iter().map(field)).boxed()
+    };
+    let dates = |field: fn(&Member) -> Option<NaiveDate>| {
+        Int32Array::from_iter(members.iter().map(|m| field(m).map(days_since_epoch)))
+            .to(DataType::Date32)
+            .boxed()
+    };
+
+    let columns: Vec<(Field, Box<dyn Array>)> = vec![
+        (
+            Field::new("index", DataType::Int64, true),
+            Int64Array::from_iter(members.iter().map(|m| m.index.map(|i| i as i64))).boxed(),
+        ),
+        (
+            Field::new("qq_name", DataType::Utf8, false),
+            strings(|m| m.qq_name.clone()),
+        ),
+        (
+            Field::new("group_name", DataType::Utf8, false),
+            strings(|m| m.group_name.clone()),
+        ),
+        (
+            Field::new("qq_number", DataType::Utf8, false),
+            strings(|m| m.qq_number.clone()),
+        ),
+        (
+            Field::new("gender", DataType::Utf8, false),
+            strings(|m| m.gender.to_string()),
+        ),
+        (
+            Field::new("qq_age", DataType::Int32, true),
+            Int32Array::from_iter(
+                members
+                    .iter()
+                    .map(|m| m.qq_age_years().and_then(|years| i32::try_from(years).ok())),
+            )
+            .boxed(),
+        ),
+        (
+            Field::new("joined_date", DataType::Date32, true),
+            dates(|m| m.joined_date),
+        ),
+        (
+            Field::new("last_spoken_date", DataType::Date32, true),
+            dates(|m| m.last_spoken_date),
+        ),
+        (
+            Field::new("avatar_url", DataType::Utf8, true),
+            Utf8Array::<i32>::from_iter(members.iter().map(|m| m.avatar_url.as_deref())).boxed(),
+        ),
+        (
+            Field::new("role", DataType::Utf8, false),
+            strings(|m| m.role.to_string()),
+        ),
+    ];
+    let (fields, arrays): (Vec<_>, Vec<_>) = columns.into_iter().unzip();
+
+    let mut writer = FileWriter::try_new(
+        writer,
+        Schema::from(fields),
+        None,
+        WriteOptions { compression: None },
+    )?;
+    writer.write(&Chunk::new(arrays), None)?;
+    writer.finish()?;
+    Ok(())
+}
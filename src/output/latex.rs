@@ -0,0 +1,57 @@
+//! LaTeX `tabular` output, for including in papers.
+
+use std::io::Write;
+
+use crate::{
+    error::OutputError,
+    qqtable::{ColumnNames, Member},
+};
+
+/// Alignment of each column of [`Member::to_csv_record`]: numbers (QQ number
+/// and Q龄) are right-aligned, text left-aligned.
+const COLUMN_SPEC: &str = "llrlrll";
+
+/// Writes `members` as a `tabular` environment under the headers in `names`,
+/// with `\hline` around the header row and after the last member.
+///
+/// Characters with a special meaning in LaTeX are escaped. Chinese text still
+/// needs a package such as `ctex` in the document.
+pub fn write_latex(
+    members: &[Member],
+    names: &ColumnNames,
+    writer: &mut impl Write,
+) -> Result<(), OutputError> {
+    writeln!(writer, "\\begin{{tabular}}{{{COLUMN_SPEC}}}")?;
+    writeln!(writer, "\\hline")?;
+    write_row(writer, names.to_array())?;
+    writeln!(writer, "\\hline")?;
+    for member in members {
+        write_row(writer, member.to_csv_record())?;
+    }
+    writeln!(writer, "\\hline")?;
+    writeln!(writer, "\\end{{tabular}}")?;
+    Ok(())
+}
+
+fn write_row<S: AsRef<str>>(writer: &mut impl Write, cells: [S; 7]) -> std::io::Result<()> {
+    let cells: Vec<_> = cells.iter().map(|cell| escape(cell.as_ref())).collect();
+    writeln!(writer, "{} \\\\", cells.join(" & "))
+}
+
+/// Escapes `s` so that LaTeX prints it as is
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,20 @@
+//! Writers for output formats other than csv.
+
+use chrono::NaiveDate;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod arrow;
+pub mod latex;
+pub mod ndjson;
+pub mod ods;
+pub mod parquet;
+pub mod sql;
+pub mod template;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod terminal;
+
+/// Days since the unix epoch, as columnar formats store dates.
+fn days_since_epoch(date: NaiveDate) -> i32 {
+    let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+    (date - epoch).num_days() as i32
+}
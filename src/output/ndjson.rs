@@ -0,0 +1,17 @@
+//! JSON Lines output, one object per member, for log pipelines and other
+//! streaming consumers.
+
+use std::io::Write;
+
+use crate::{error::OutputError, qqtable::Member};
+
+/// Writes each of `members` as a JSON object on its own line. Unlike a JSON
+/// array, there is nothing around the objects, and every line, including the
+/// last, ends with `\n`.
+pub fn write_ndjson(members: &[Member], writer: &mut impl Write) -> Result<(), OutputError> {
+    for member in members {
+        serde_json::to_writer(&mut *writer, member)?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
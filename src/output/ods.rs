@@ -0,0 +1,43 @@
+//! OpenDocument spreadsheet output, for LibreOffice users.
+
+use std::path::Path;
+
+use spreadsheet_ods::{format, CellStyle, Sheet, WorkBook};
+
+use crate::{
+    error::OutputError,
+    qqtable::{ColumnNames, Member},
+};
+
+/// Writes `members` to a new single-sheet workbook at `path`, with the
+/// headers in `names` in the first row.
+///
+/// Join and last spoken dates are written as date cells, so that they can be
+/// sorted and computed with. Unknown dates are left empty.
+pub fn write_ods(members: &[Member], names: &ColumnNames, path: &Path) -> Result<(), OutputError> {
+    let mut book = WorkBook::default();
+    let date_format = book.add_datetime_format(format::create_date_iso_format("qq_date"));
+    let date_style = book.add_cellstyle(CellStyle::new("qq_date", &date_format));
+
+    let mut sheet = Sheet::new("群成员");
+    for (col, header) in (0..).zip(names.to_array()) {
+        sheet.set_value(0, col, header);
+    }
+    for (row, member) in (1..).zip(members) {
+        sheet.set_value(row, 0, member.qq_name.as_str());
+        sheet.set_value(row, 1, member.group_name.as_str());
+        sheet.set_value(row, 2, member.qq_number.as_str());
+        sheet.set_value(row, 3, member.gender.to_string());
+        sheet.set_value(row, 4, member.qq_age.as_str());
+        if let Some(date) = member.joined_date {
+            sheet.set_styled_value(row, 5, date, &date_style);
+        }
+        if let Some(date) = member.last_spoken_date {
+            sheet.set_styled_value(row, 6, date, &date_style);
+        }
+    }
+    book.push_sheet(sheet);
+
+    spreadsheet_ods::write_ods(&mut book, path)?;
+    Ok(())
+}
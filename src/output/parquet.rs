@@ -0,0 +1,117 @@
+//! Apache Parquet output, for analytics tools like DuckDB, Spark and Pandas.
+
+use std::{fs::File, path::Path, sync::Arc};
+
+use parquet::{
+    data_type::{ByteArray, ByteArrayType, DataType, Int32Type, Int64Type},
+    file::{
+        properties::WriterProperties,
+        writer::{SerializedFileWriter, SerializedRowGroupWriter},
+    },
+    schema::{parser::parse_message_type, types::SchemaDescriptor},
+};
+
+use super::days_since_epoch;
+use crate::{error::OutputError, qqtable::Member};
+
+/// One column for each field of [`Member`]. Dates are days since the unix
+/// epoch, and `qq_age` is the number of years, see [`Member::qq_age_years`].
+const SCHEMA: &str = "
+message member {
+    OPTIONAL INT64 index;
+    REQUIRED BYTE_ARRAY qq_name (UTF8);
+    REQUIRED BYTE_ARRAY group_name (UTF8);
+    REQUIRED BYTE_ARRAY qq_number (UTF8);
+    REQUIRED BYTE_ARRAY gender (UTF8);
+    OPTIONAL INT32 qq_age;
+    OPTIONAL INT32 joined_date (DATE);
+    OPTIONAL INT32 last_spoken_date (DATE);
+    OPTIONAL BYTE_ARRAY avatar_url (UTF8);
+    REQUIRED BYTE_ARRAY role (UTF8);
+}
+";
+
+/// Writes `members` to a new parquet file at `path`, as a single row group.
+///
+/// Gender and role are written as in csv, e.g. `女` and `管理员`.
+pub fn write_parquet(members: &[Member], path: &Path) -> Result<(), OutputError> {
+    let schema = SchemaDescriptor::new(Arc::new(
+        parse_message_type(SCHEMA).expect("schema should be valid"),
+    ));
+    let file = File::create(path)?;
+    let mut writer = SerializedFileWriter::new(
+        file,
+        schema.root_schema_ptr(),
+        Arc::new(WriterProperties::builder().build()),
+    )?;
+
+    let mut row_group = writer.next_row_group()?;
+    write_column::<Int64Type>(
+        &mut row_group,
+        members.iter().map(|m| m.index.map(|i| i as i64)),
+    )?;
+    write_column::<ByteArrayType>(&mut row_group, members.iter().map(|m| text(&m.qq_name)))?;
+    write_column::<ByteArrayType>(&mut row_group, members.iter().map(|m| text(&m.group_name)))?;
+    write_column::<ByteArrayType>(&mut row_group, members.iter().map(|m| text(&m.qq_number)))?;
+    write_column::<ByteArrayType>(
+        &mut row_group,
+        members.iter().map(|m| text(&m.gender.to_string())),
+    )?;
+    write_column::<Int32Type>(
+        &mut row_group,
+        members
+            .iter()
+            .map(|m| m.qq_age_years().and_then(|years| i32::try_from(years).ok())),
+    )?;
+    write_column::<Int32Type>(
+        &mut row_group,
+        members.iter().map(|m| m.joined_date.map(days_since_epoch)),
+    )?;
+    write_column::<Int32Type>(
+        &mut row_group,
+        members
+            .iter()
+            .map(|m| m.last_spoken_date.map(days_since_epoch)),
+    )?;
+    write_column::<ByteArrayType>(
+        &mut row_group,
+        members
+            .iter()
+            .map(|m| m.avatar_url.as_deref().and_then(text)),
+    )?;
+    write_column::<ByteArrayType>(
+        &mut row_group,
+        members.iter().map(|m| text(&m.role.to_string())),
+    )?;
+    row_group.close()?;
+
+    writer.close()?;
+    Ok(())
+}
+
+/// Writes the next column of `row_group`, with a null for each `None`.
+fn write_column<T: DataType>(
+    row_group: &mut SerializedRowGroupWriter<'_, File>,
+    values: impl Iterator<Item = Option<T::T>>,
+) -> Result<(), OutputError> {
+    let mut column = row_group
+        .next_column()?
+        .expect("schema should have a column for each field");
+    let writer = column.typed::<T>();
+
+    let mut def_levels = Vec::new();
+    let mut present = Vec::new();
+    for value in values {
+        def_levels.push(i16::from(value.is_some()));
+        present.extend(value);
+    }
+    let def_levels = (writer.get_descriptor().max_def_level() > 0).then_some(&def_levels[..]);
+    writer.write_batch(&present, def_levels, None)?;
+    column.close()?;
+    Ok(())
+}
+
+/// A non-null string value.
+fn text(s: &str) -> Option<ByteArray> {
+    Some(ByteArray::from(s))
+}
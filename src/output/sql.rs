@@ -0,0 +1,103 @@
+//! SQL output, for bulk loading members into MySQL or PostgreSQL.
+
+use std::io::Write;
+
+use crate::{error::OutputError, qqtable::Member};
+use chrono::NaiveDate;
+
+/// Number of rows in each `INSERT` statement, to keep statements well below
+/// the size limits of common servers.
+const BATCH_SIZE: usize = 500;
+
+const COLUMNS: [&str; 9] = [
+    "qq_number",
+    "qq_name",
+    "group_name",
+    "gender",
+    "qq_age",
+    "joined_date",
+    "last_spoken_date",
+    "avatar_url",
+    "role",
+];
+
+/// Writes a `CREATE TABLE IF NOT EXISTS` statement for `table_name`, followed
+/// by `INSERT` statements for `members` of up to 500 rows each.
+///
+/// `table_name` must be a plain identifier (ASCII letters, digits and `_`, not
+/// starting with a digit), since it is written without quotes. Strings are
+/// escaped by doubling single quotes, which is what standard SQL expects;
+/// MySQL also needs `NO_BACKSLASH_ESCAPES` for names containing `\`.
+pub fn write_sql(
+    members: &[Member],
+    table_name: &str,
+    writer: &mut impl Write,
+) -> Result<(), OutputError> {
+    if !is_identifier(table_name) {
+        return Err(OutputError::InvalidTableName(table_name.to_owned()));
+    }
+
+    writeln!(
+        writer,
+        "CREATE TABLE IF NOT EXISTS {table_name} (
+    qq_number VARCHAR(20) NOT NULL,
+    qq_name VARCHAR(255) NOT NULL,
+    group_name VARCHAR(255) NOT NULL,
+    gender VARCHAR(8) NOT NULL,
+    qq_age VARCHAR(16) NOT NULL,
+    joined_date DATE,
+    last_spoken_date DATE,
+    avatar_url TEXT,
+    role VARCHAR(8) NOT NULL
+);"
+    )?;
+
+    for batch in members.chunks(BATCH_SIZE) {
+        writeln!(
+            writer,
+            "INSERT INTO {table_name} ({}) VALUES",
+            COLUMNS.join(", ")
+        )?;
+        for (i, member) in batch.iter().enumerate() {
+            let end = if i + 1 == batch.len() { ";" } else { "," };
+            writeln!(writer, "    ({}){end}", values(member).join(", "))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The SQL literals for `member`, in the order of [`COLUMNS`].
+fn values(member: &Member) -> [String; 9] {
+    [
+        quote(&member.qq_number),
+        quote(&member.qq_name),
+        quote(&member.group_name),
+        quote(&member.gender.to_string()),
+        quote(&member.qq_age),
+        date(member.joined_date),
+        date(member.last_spoken_date),
+        member.avatar_url.as_deref().map_or_else(null, quote),
+        quote(&member.role.to_string()),
+    ]
+}
+
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+fn date(date: Option<NaiveDate>) -> String {
+    date.map_or_else(null, |d| format!("'{}'", d.format("%Y-%m-%d")))
+}
+
+fn null() -> String {
+    "NULL".to_owned()
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
@@ -0,0 +1,58 @@
+//! Output rendered from Handlebars templates, for custom reports.
+
+use std::io::Write;
+
+use handlebars::Handlebars;
+
+use crate::{
+    error::OutputError,
+    qqtable::{ColumnNames, Member},
+};
+
+/// The row of [`write_html_table`] for each member.
+const HTML_ROW_TEMPLATE: &str = "<tr><td>{{display_name}}</td><td>{{group_nickname}}</td>\
+<td>{{qq_number}}</td><td>{{gender}}</td><td>{{qq_age}}</td><td>{{joined_date}}</td>\
+<td>{{last_spoken}}</td></tr>\n";
+
+/// Renders `template` once for each of `members`, one after the other. The
+/// fields of a member are available by the names of
+/// [`Member::to_hashmap`], e.g. `{{display_name}}`.
+///
+/// Values are HTML-escaped as usual in Handlebars; use `{{{display_name}}}` to
+/// write them as is. Referring to a field that doesn't exist is an error.
+pub fn write_template(
+    members: &[Member],
+    template: &str,
+    writer: &mut impl Write,
+) -> Result<(), OutputError> {
+    let mut registry = Handlebars::new();
+    registry.set_strict_mode(true);
+    registry.register_template_string("member", template)?;
+
+    for member in members {
+        registry.render_to_write("member", &member.to_hashmap(), &mut *writer)?;
+    }
+    Ok(())
+}
+
+/// Writes `members` as a standalone HTML page with a table under the headers
+/// in `names`, using [`write_template`] for the rows.
+pub fn write_html_table(
+    members: &[Member],
+    names: &ColumnNames,
+    writer: &mut impl Write,
+) -> Result<(), OutputError> {
+    let header: String = names
+        .to_array()
+        .iter()
+        .map(|name| format!("<th>{}</th>", handlebars::html_escape(name)))
+        .collect();
+    write!(
+        writer,
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"></head>\n<body>\n\
+<table>\n<thead>\n<tr>{header}</tr>\n</thead>\n<tbody>\n"
+    )?;
+    write_template(members, HTML_ROW_TEMPLATE, writer)?;
+    writer.write_all(b"</tbody>\n</table>\n</body>\n</html>\n")?;
+    Ok(())
+}
@@ -0,0 +1,46 @@
+//! Aligned tables for reading in a terminal.
+
+use std::{env, io::Write};
+
+use crate::{
+    error::OutputError,
+    qqtable::{ColumnNames, Member},
+};
+use comfy_table::{presets::UTF8_FULL, Attribute, Cell, ContentArrangement, Row, Table};
+
+/// Writes `members` as a table drawn with Unicode box-drawing characters, under
+/// the headers in `names`.
+///
+/// This is meant for stdout: if stdout is a terminal, the table is fitted to
+/// its width, with long names cut short, and the header is bold unless
+/// `NO_COLOR` is set.
+pub fn write_terminal_table(
+    members: &[Member],
+    names: &ColumnNames,
+    writer: &mut impl Write,
+) -> Result<(), OutputError> {
+    // https://no-color.org: any non-empty value disables color
+    let no_color = env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL)
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(names.to_array().map(|header| {
+            let cell = Cell::new(header);
+            if no_color {
+                cell
+            } else {
+                cell.add_attribute(Attribute::Bold)
+            }
+        }));
+    for member in members {
+        let mut row = Row::from(member.to_csv_record());
+        // truncate instead of wrapping when the terminal is too narrow
+        row.max_height(1);
+        table.add_row(row);
+    }
+
+    writeln!(writer, "{table}")?;
+    Ok(())
+}
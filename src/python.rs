@@ -0,0 +1,173 @@
+//! Python bindings, built with `maturin build --features python`.
+//!
+//! ```python
+//! import qq_group_name_extract as qq
+//!
+//! members = qq.parse_html(open("group.html", encoding="utf-8").read())
+//! ```
+
+use std::path::PathBuf;
+
+use chrono::NaiveDate;
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+use crate::{
+    error::{display_chain, QqExtractError},
+    group::GroupExport,
+    qqtable::{Gender, Member, Role},
+};
+
+/// A group member, with the same fields as the rust [`Member`]. `gender` and
+/// `role` are lowercase strings, e.g. `"female"` and `"admin"`.
+#[pyclass(name = "Member", module = "qq_group_name_extract", frozen, eq)]
+#[derive(Clone, PartialEq)]
+pub struct PyMember(Member);
+
+#[pymethods]
+impl PyMember {
+    #[getter]
+    fn index(&self) -> Option<usize> {
+        self.0.index
+    }
+
+    #[getter]
+    fn qq_name(&self) -> &str {
+        &self.0.qq_name
+    }
+
+    #[getter]
+    fn group_name(&self) -> &str {
+        &self.0.group_name
+    }
+
+    #[getter]
+    fn qq_number(&self) -> &str {
+        &self.0.qq_number
+    }
+
+    #[getter]
+    fn gender(&self) -> &'static str {
+        match self.0.gender {
+            Gender::Male => "male",
+            Gender::Female => "female",
+            Gender::Unknown => "unknown",
+        }
+    }
+
+    #[getter]
+    fn qq_age(&self) -> &str {
+        &self.0.qq_age
+    }
+
+    #[getter]
+    fn joined_date(&self) -> Option<NaiveDate> {
+        self.0.joined_date
+    }
+
+    #[getter]
+    fn last_spoken_date(&self) -> Option<NaiveDate> {
+        self.0.last_spoken_date
+    }
+
+    #[getter]
+    fn avatar_url(&self) -> Option<&str> {
+        self.0.avatar_url.as_deref()
+    }
+
+    #[getter]
+    fn role(&self) -> &'static str {
+        match self.0.role {
+            Role::Owner => "owner",
+            Role::Admin => "admin",
+            Role::Member => "member",
+        }
+    }
+
+    #[getter]
+    fn profile_url(&self) -> String {
+        self.0.profile_url()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Member(qq_number={:?}, qq_name={:?}, group_name={:?}, gender={:?}, role={:?})",
+            self.0.qq_number,
+            self.0.qq_name,
+            self.0.group_name,
+            self.gender(),
+            self.role(),
+        )
+    }
+}
+
+/// The members of one group, see the rust [`GroupExport`].
+#[pyclass(name = "GroupExport", module = "qq_group_name_extract", frozen, eq)]
+#[derive(Clone, PartialEq)]
+pub struct PyGroupExport(GroupExport);
+
+#[pymethods]
+impl PyGroupExport {
+    #[getter]
+    fn group_id(&self) -> Option<&str> {
+        self.0.group_id.as_deref()
+    }
+
+    #[getter]
+    fn group_name(&self) -> Option<&str> {
+        self.0.group_name.as_deref()
+    }
+
+    #[getter]
+    fn members(&self) -> Vec<PyMember> {
+        self.0.members.iter().cloned().map(PyMember).collect()
+    }
+
+    #[getter]
+    fn source_path(&self) -> Option<PathBuf> {
+        self.0.source_path.clone()
+    }
+
+    fn __len__(&self) -> usize {
+        self.0.members.len()
+    }
+
+    fn __repr__(&self) -> String {
+        let group_id = match &self.0.group_id {
+            Some(id) => format!("{:?}", id),
+            None => "None".to_owned(),
+        };
+        format!(
+            "GroupExport(group_id={}, members=<{} members>)",
+            group_id,
+            self.0.members.len()
+        )
+    }
+}
+
+impl From<QqExtractError> for PyErr {
+    fn from(err: QqExtractError) -> PyErr {
+        // python only shows the message, so it includes the causes too
+        PyValueError::new_err(display_chain(&err))
+    }
+}
+
+/// Parses the members in the html of `https://qun.qq.com/member.html`.
+#[pyfunction]
+fn parse_html(html: &str) -> PyResult<Vec<PyMember>> {
+    Ok(Member::from_html(html)?.into_iter().map(PyMember).collect())
+}
+
+/// Like `parse_html`, but also reads the group number from the page.
+#[pyfunction]
+fn parse_group_html(html: &str) -> PyResult<PyGroupExport> {
+    Ok(PyGroupExport(GroupExport::from_html(html)?))
+}
+
+#[pymodule]
+fn qq_group_name_extract(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyMember>()?;
+    m.add_class::<PyGroupExport>()?;
+    m.add_function(wrap_pyfunction!(parse_html, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_group_html, m)?)?;
+    Ok(())
+}
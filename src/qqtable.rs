@@ -1,24 +1,83 @@
-use std::fmt;
+use std::{cmp::Ordering, collections::HashMap, fmt, io::Write, sync::LazyLock};
 
-use eyre::{eyre, Context, Result};
-use lazy_static::lazy_static;
-use log::{debug, trace};
+use chrono::{Duration, NaiveDate, Utc};
 use scraper::{Html, Selector};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tracing::{debug, instrument, trace, warn};
 
-use crate::table::Table;
+use crate::{
+    error::QqExtractError,
+    table::{CellMode, Row, Table, TableConfig},
+};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
 pub struct Member {
+    /// The row number shown in the `序号` column of the member page.
+    pub index: Option<usize>,
     pub qq_name: String,
     pub group_name: String,
     pub qq_number: String,
     pub gender: Gender,
     pub qq_age: String,
-    pub joined_date: String,
-    pub last_spoken_date: String,
+    pub joined_date: Option<NaiveDate>,
+    pub last_spoken_date: Option<NaiveDate>,
+    pub avatar_url: Option<String>,
+    pub role: Role,
 }
 
-#[derive(Debug)]
+/// The canonical ordering of members: by join date, with unknown dates last,
+/// then by QQ number.
+///
+/// QQ numbers are digit strings, so they are compared by length first to sort
+/// them numerically. The remaining fields only break ties between members that
+/// would otherwise be equal, so that the ordering agrees with `Eq`. To sort by
+/// any other field, use `sort_by_key` instead.
+impl Ord for Member {
+    fn cmp(&self, other: &Self) -> Ordering {
+        fn joined(m: &Member) -> (bool, Option<NaiveDate>) {
+            (m.joined_date.is_none(), m.joined_date)
+        }
+        fn qq_number(m: &Member) -> (usize, &str) {
+            (m.qq_number.len(), &m.qq_number)
+        }
+
+        joined(self)
+            .cmp(&joined(other))
+            .then_with(|| qq_number(self).cmp(&qq_number(other)))
+            .then_with(|| {
+                (
+                    self.index,
+                    &self.qq_name,
+                    &self.group_name,
+                    &self.gender,
+                    &self.qq_age,
+                    self.last_spoken_date,
+                    &self.avatar_url,
+                    &self.role,
+                )
+                    .cmp(&(
+                        other.index,
+                        &other.qq_name,
+                        &other.group_name,
+                        &other.gender,
+                        &other.qq_age,
+                        other.last_spoken_date,
+                        &other.avatar_url,
+                        &other.role,
+                    ))
+            })
+    }
+}
+
+impl PartialOrd for Member {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Gender {
     Male,
     Female,
@@ -39,137 +98,751 @@ impl fmt::Display for Gender {
     }
 }
 
-fn get_header<'a>(
-    cell: &[&'a String],
-    header: &'static str,
-    row_index: usize,
-    cell_index: usize,
-) -> Result<&'a String> {
-    cell.get(cell_index)
-        .ok_or_else(|| {
-            eyre!(format!(
-                "Failed to get value for header `{header}`, at row `{row_index}`"
-            ))
-        })
-        .map(|s| *s)
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Owner,
+    Admin,
+    Member,
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Role::Owner => "群主",
+                Role::Admin => "管理员",
+                Role::Member => "成员",
+            }
+        )
+    }
+}
+
+const QQ_DATE_FMT: &str = "%Y/%m/%d";
+
+/// Parses a date as shown on the member page, e.g. `2021/11/01`.
+///
+/// `未知` and empty cells mean the date is not known, and give `None`. So does
+/// anything else that isn't a date, after logging a warning.
+pub fn parse_qq_date(s: &str) -> Option<NaiveDate> {
+    try_parse_qq_date(s)
+        .map_err(|e| warn!("{e}"))
+        .ok()
+        .flatten()
+}
+
+/// Like [`parse_qq_date`], but anything other than a date, `未知` or an empty
+/// cell is an error.
+pub fn try_parse_qq_date(s: &str) -> Result<Option<NaiveDate>, QqExtractError> {
+    let s = s.trim();
+    if s.is_empty() || s == "未知" {
+        return Ok(None);
+    }
+
+    NaiveDate::parse_from_str(s, QQ_DATE_FMT)
+        .map(Some)
+        .map_err(|_| QqExtractError::InvalidDate(s.to_owned()))
+}
+
+/// Formats a date the same way the member page does, with `未知` for `None`.
+pub fn format_qq_date(date: Option<NaiveDate>) -> String {
+    date.map_or_else(|| "未知".to_owned(), |d| d.format(QQ_DATE_FMT).to_string())
+}
+
+/// Checks that `s` looks like a QQ number, i.e. is a non-empty string of ASCII
+/// digits, and returns it.
+pub fn validate_qq_number(s: &str) -> Result<&str, QqExtractError> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(QqExtractError::InvalidQqNumber(s.to_owned()));
+    }
+    Ok(s)
+}
+
+/// Extracts the avatar url from the `成员` cell, adding `https:` to the
+/// protocol-relative `//q4.qlogo.cn/...` urls used by the page.
+fn avatar_url_from_cell(cell: &Html) -> Option<String> {
+    let src = cell.select(&AVATAR_SLT).next()?.value().attr("src")?.trim();
+    if src.is_empty() {
+        return None;
+    }
+
+    let url = if let Some(rest) = src.strip_prefix("//") {
+        format!("https://{rest}")
+    } else if let Some(rest) = src.strip_prefix("http://") {
+        format!("https://{rest}")
+    } else {
+        src.to_owned()
+    };
+    Some(url)
+}
+
+/// Finds the member's role from the badge in front of their name in the `成员`
+/// cell
+fn role_from_cell(cell: &Html) -> Role {
+    if cell.select(&OWNER_SLT).next().is_some() {
+        Role::Owner
+    } else if cell.select(&ADMIN_SLT).next().is_some() {
+        Role::Admin
+    } else {
+        Role::Member
+    }
 }
 
-lazy_static! {
-    static ref QQ_NAME_SLT: Selector = Selector::parse("span").unwrap();
-    static ref GROUP_NAME_SLT: Selector = Selector::parse("span").unwrap();
+/// The names of the columns of [`Member::to_csv_record`] in one language, for
+/// output headers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ColumnNames {
+    pub qq_name: &'static str,
+    pub group_name: &'static str,
+    pub qq_number: &'static str,
+    pub gender: &'static str,
+    pub qq_age: &'static str,
+    pub joined_date: &'static str,
+    pub last_spoken_date: &'static str,
+}
+
+impl ColumnNames {
+    /// The headers of the member page, which are also [`Member::csv_header`].
+    pub const ZH: ColumnNames = ColumnNames {
+        qq_name: "成员",
+        group_name: "群昵称",
+        qq_number: "QQ号",
+        gender: "性别",
+        qq_age: "Q龄",
+        joined_date: "入群时间",
+        last_spoken_date: "最后发言",
+    };
+
+    pub const EN: ColumnNames = ColumnNames {
+        qq_name: "display_name",
+        group_name: "group_nickname",
+        qq_number: "qq_number",
+        gender: "gender",
+        qq_age: "qq_age",
+        joined_date: "joined_date",
+        last_spoken_date: "last_spoken",
+    };
+
+    /// The names in the order of [`Member::to_csv_record`].
+    pub fn to_array(&self) -> [&'static str; 7] {
+        [
+            self.qq_name,
+            self.group_name,
+            self.qq_number,
+            self.gender,
+            self.qq_age,
+            self.joined_date,
+            self.last_spoken_date,
+        ]
+    }
+
+    /// Translates `header`, one of the Chinese [`ColumnNames::ZH`], to this
+    /// language. Returns `None` for any other header.
+    pub fn translate(&self, header: &str) -> Option<&'static str> {
+        ColumnNames::ZH
+            .to_array()
+            .iter()
+            .position(|&h| h == header)
+            .map(|i| self.to_array()[i])
+    }
+}
+
+const MEMBER_TABLE_ID: &str = "groupMember";
+
+/// Where [`Member::from_html_with_config`] finds the members: the id of the
+/// member table and the zero-based index of each column in it.
+///
+/// [`ParseConfig::DEFAULT`] matches the layout of
+/// `https://qun.qq.com/member.html`; pages with a slightly different layout can
+/// start from it and override what differs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ParseConfig {
+    pub table_id: &'static str,
+    /// The `序号` cell, numbering the members from 1.
+    pub index_col: usize,
+    /// The cell with the avatar, role badge and QQ name of the member.
+    pub name_col: usize,
+    pub group_name_col: usize,
+    pub qq_number_col: usize,
+    pub gender_col: usize,
+    pub qq_age_col: usize,
+    pub joined_date_col: usize,
+    pub last_spoken_col: usize,
+    /// Use the first table of the page, or the one at `fallback_table_index`,
+    /// when none has the id `table_id`, as some versions of the member page
+    /// leave it out. Off by default, since the first table is not always the
+    /// member table.
+    pub fallback_to_first_table: bool,
+    /// The zero-based index of the table that `fallback_to_first_table` falls
+    /// back to. 0, the first table, by default.
+    pub fallback_table_index: usize,
+}
+
+impl ParseConfig {
+    pub const DEFAULT: ParseConfig = ParseConfig {
+        table_id: MEMBER_TABLE_ID,
+        index_col: 1,
+        name_col: 2,
+        group_name_col: 3,
+        qq_number_col: 4,
+        gender_col: 5,
+        qq_age_col: 6,
+        joined_date_col: 7,
+        last_spoken_col: 8,
+        fallback_to_first_table: false,
+        fallback_table_index: 0,
+    };
+
+    /// [`ParseConfig::DEFAULT`], but falling back to the `table_index`th
+    /// (zero-based) table when there is no `groupMember` table.
+    pub(crate) const fn or_nth(table_index: usize) -> ParseConfig {
+        ParseConfig {
+            fallback_to_first_table: true,
+            fallback_table_index: table_index,
+            ..ParseConfig::DEFAULT
+        }
+    }
+}
+
+impl Default for ParseConfig {
+    fn default() -> Self {
+        ParseConfig::DEFAULT
+    }
+}
+
+/// A cell of the member table with a value the member page wouldn't show, as
+/// found by [`Member::validate_html`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Violation {
+    /// The row of the member, counting from 0 as in
+    /// [`QqExtractError::MissingCell`].
+    pub row: usize,
+    /// The header of the column, e.g. `QQ号`.
+    pub field: &'static str,
+    /// The text of the cell, empty if the row has no such cell.
+    pub value: String,
+    /// What is wrong with it.
+    pub problem: &'static str,
+}
+
+// Parsed once on first use rather than on every row, since a group can have
+// thousands of members.
+static QQ_NAME_SLT: LazyLock<Selector> = LazyLock::new(|| Selector::parse("span").unwrap());
+static TABLE_SLT: LazyLock<Selector> = LazyLock::new(|| Selector::parse("table").unwrap());
+static AVATAR_SLT: LazyLock<Selector> = LazyLock::new(|| Selector::parse("img[src]").unwrap());
+static OWNER_SLT: LazyLock<Selector> =
+    LazyLock::new(|| Selector::parse("a.group-master-a").unwrap());
+static ADMIN_SLT: LazyLock<Selector> =
+    LazyLock::new(|| Selector::parse("a.group-manage-a").unwrap());
+
+/// Finds the member table of `document` as `config` says.
+///
+/// Only the `成员` cells are read as HTML, for the name, the avatar and the
+/// role badge. The other cells are read as text, with entities decoded.
+fn member_table(document: &Html, config: &ParseConfig) -> Option<Table> {
+    let table_config = TableConfig {
+        cell_mode: CellMode::TextOnly,
+        inner_html_column: Some(config.name_col),
+    };
+    let by_id = Selector::parse(&format!("table#{}", config.table_id)).ok();
+    let element = by_id.and_then(|s| document.select(&s).next()).or_else(|| {
+        if !config.fallback_to_first_table {
+            return None;
+        }
+        warn!(
+            "No `{}` table, using table at index {} instead",
+            config.table_id, config.fallback_table_index
+        );
+        document.select(&TABLE_SLT).nth(config.fallback_table_index)
+    })?;
+    Some(Table::with_config(element, &table_config))
 }
 
 impl Member {
-    pub fn from_html(html: &str) -> Result<Vec<Self>> {
+    /// Returns `true` if the member last spoke no more than `threshold` ago.
+    ///
+    /// Members without a last spoken date are treated as inactive.
+    pub fn is_active(&self, threshold: Duration) -> bool {
+        let today = Utc::now().naive_utc().date();
+        self.last_spoken_date
+            .is_some_and(|date| today - date <= threshold)
+    }
+
+    /// Returns the number of days from the member's last spoken date to
+    /// `today`, or `None` if it isn't known.
+    pub fn days_since_last_spoken(&self, today: NaiveDate) -> Option<i64> {
+        self.last_spoken_date.map(|date| (today - date).num_days())
+    }
+
+    /// Returns the number of complete years from the member's join date to
+    /// `today`, or `None` if the join date isn't known or is after `today`.
+    ///
+    /// This is usually the same as [`qq_age_years`](Self::qq_age_years), but
+    /// is computed from the join date rather than read from Q龄.
+    pub fn joined_years_ago(&self, today: NaiveDate) -> Option<u32> {
+        today.years_since(self.joined_date?)
+    }
+
+    /// Returns the number of years in Q龄, e.g. `15` for `15年`, or `None` if
+    /// it isn't a number of years.
+    pub fn qq_age_years(&self) -> Option<u32> {
+        self.qq_age.trim().strip_suffix('年')?.parse().ok()
+    }
+
+    /// Returns the link to the member's QQ profile page.
+    ///
+    /// Returns an empty string if the QQ number isn't all digits.
+    pub fn profile_url(&self) -> String {
+        match validate_qq_number(&self.qq_number) {
+            Ok(qq_number) => format!("https://user.qzone.qq.com/{qq_number}"),
+            Err(e) => {
+                warn!("Not building profile url: {e}");
+                String::new()
+            }
+        }
+    }
+
+    /// Replaces the QQ number with the first 16 hex digits of the SHA-256 of
+    /// `salt` followed by the number, and the QQ name with `用户` and that
+    /// hash. The avatar url is dropped, since it contains the number.
+    ///
+    /// The same salt always gives the same hash for a number, so anonymized
+    /// members can still be matched up across files.
+    pub fn anonymize(&mut self, salt: &str) {
+        let digest = Sha256::new()
+            .chain_update(salt)
+            .chain_update(&self.qq_number)
+            .finalize();
+        let hash: String = digest[..8].iter().map(|b| format!("{b:02x}")).collect();
+
+        self.qq_name = format!("用户{hash}");
+        self.qq_number = hash;
+        self.avatar_url = None;
+    }
+
+    /// Parses the members out of the `groupMember` table, for the bindings.
+    /// [`GroupExport::from_html`](crate::group::GroupExport::from_html) reads
+    /// the group info from the same parse with [`Member::from_document`]
+    /// instead.
+    #[cfg(any(feature = "python", feature = "wasm", test))]
+    #[instrument(skip_all)]
+    pub(crate) fn from_html(html: &str) -> Result<Vec<Self>, QqExtractError> {
         trace!("---html---\n{:#?}", html);
+        Self::from_html_with_config(html, &ParseConfig::DEFAULT)
+    }
 
-        // let html_parsed = Html::parse_fragment(html);
-        // let table_selector =
-        //     Selector::parse("table").expect("failed to parse css selector (should not happen!)");
+    /// Like [`GroupExport::from_html`](crate::group::GroupExport::from_html),
+    /// but only the members, with the member table and its columns located as
+    /// `config` says.
+    pub fn from_html_with_config(
+        html: &str,
+        config: &ParseConfig,
+    ) -> Result<Vec<Self>, QqExtractError> {
+        Self::from_document(&Html::parse_document(html), config)
+    }
+
+    /// Like [`Member::from_html_with_config`], but on a page that is already
+    /// parsed, so that [`GroupExport`](crate::group::GroupExport) can read the
+    /// group number and name from the same parse.
+    pub(crate) fn from_document(
+        document: &Html,
+        config: &ParseConfig,
+    ) -> Result<Vec<Self>, QqExtractError> {
+        let mut members = Vec::new();
+        Self::for_each_in_document(document, config, |member| {
+            members.push(member);
+            Ok(())
+        })?;
+
+        Ok(members)
+    }
 
-        // let html_tables: Vec<_> = html_parsed
-        //     .select(&table_selector)
-        //     .map(Table::new)
-        //     .collect();
+    /// Like [`Member::from_html_with_config`] with the default config, but if
+    /// there is no `groupMember` table, falls back to the `table_index`th
+    /// (zero-based) table of the document.
+    pub fn from_html_or_nth(html: &str, table_index: usize) -> Result<Vec<Self>, QqExtractError> {
+        Self::from_html_with_config(html, &ParseConfig::or_nth(table_index))
+    }
 
-        // let mbr_slt = Selector::parse("tr.mb").expect("failed to parse tr selector");
-        // info!(
-        //     "Num members found: {}",
-        //     html_parsed.select(&mbr_slt).count()
-        // );
+    /// Parses the members out of the `groupMember` table and writes them to
+    /// `writer` as csv, one row at a time instead of collecting them first.
+    ///
+    /// Returns the number of members written, not counting the header row.
+    pub fn parse_html_into<W: Write>(html: &str, writer: W) -> Result<usize, QqExtractError> {
+        trace!("---html---\n{:#?}", html);
 
-        // info!(
-        //     "Table vec should have a len of 1. Actual len: {}",
-        //     &html_tables.len()
-        // );
+        let mut wtr = csv::Writer::from_writer(writer);
+        wtr.write_record(Self::csv_header())?;
+        let document = Html::parse_document(html);
+        let count = Self::for_each_in_document(&document, &ParseConfig::DEFAULT, |member| {
+            Ok(wtr.write_record(member.to_csv_record())?)
+        })?;
+        wtr.flush().map_err(csv::Error::from)?;
 
-        // let table = &html_tables
-        //     .first()
-        //     .wrap_err("Can't get first element of html table select")?;
+        Ok(count)
+    }
 
-        let table = Table::find_by_id(html, "groupMember")
-            .ok_or_else(|| eyre!("Failed to extract table"))?;
+    /// Checks every row of the `groupMember` table for values that wouldn't
+    /// parse or don't look like the member page: QQ numbers that aren't
+    /// digits, join dates that aren't dates, last spoken dates that are
+    /// neither dates nor `未知`, Q龄 other than a number of `年`, and unknown
+    /// genders.
+    ///
+    /// Unlike [`Member::from_html_with_config`], this doesn't stop at the first
+    /// bad row.
+    pub fn validate_html(html: &str) -> Result<Vec<Violation>, QqExtractError> {
+        let config = ParseConfig::DEFAULT;
+        let document = Html::parse_document(html);
+        let table = member_table(&document, &config).ok_or(QqExtractError::TableNotFound)?;
 
+        // each check gives the problem with a cell
+        type Check = fn(&str) -> Result<(), &'static str>;
+        let checks: [(&str, usize, Check); 5] = [
+            ("QQ号", config.qq_number_col, |s| {
+                validate_qq_number(s.trim())
+                    .map(drop)
+                    .map_err(|_| "not a number")
+            }),
+            (
+                "入群时间",
+                config.joined_date_col,
+                |s| match try_parse_qq_date(s) {
+                    Ok(Some(_)) => Ok(()),
+                    _ => Err("not a date"),
+                },
+            ),
+            ("最后发言", config.last_spoken_col, |s| {
+                try_parse_qq_date(s)
+                    .map(drop)
+                    .map_err(|_| "neither a date nor 未知")
+            }),
+            ("Q龄", config.qq_age_col, |s| {
+                let years = s.trim().strip_suffix('年').unwrap_or("");
+                if !years.is_empty() && years.bytes().all(|b| b.is_ascii_digit()) {
+                    Ok(())
+                } else {
+                    Err("not a number of years")
+                }
+            }),
+            ("性别", config.gender_col, |s| match s {
+                "男" | "女" | "未知" => Ok(()),
+                _ => Err("not 男, 女 or 未知"),
+            }),
+        ];
+
+        let mut violations = Vec::new();
+        for (row, cells) in table.iter().enumerate() {
+            for (field, col, check) in checks {
+                let (value, result) = match cells.get_by_index(col) {
+                    Some(value) => (value, check(value)),
+                    None => ("", Err("missing")),
+                };
+                if let Err(problem) = result {
+                    violations.push(Violation {
+                        row,
+                        field,
+                        value: value.to_owned(),
+                        problem,
+                    });
+                }
+            }
+        }
+        Ok(violations)
+    }
+
+    /// Finds the member table of `document` as `config` says and passes its
+    /// members to `f` one by one, see [`Member::for_each_in_table`]. Both
+    /// [`Member::from_document`] and [`Member::parse_html_into`] go through
+    /// here.
+    fn for_each_in_document(
+        document: &Html,
+        config: &ParseConfig,
+        f: impl FnMut(Self) -> Result<(), QqExtractError>,
+    ) -> Result<usize, QqExtractError> {
+        let table = member_table(document, config).ok_or(QqExtractError::TableNotFound)?;
+
+        Self::for_each_in_table(&table, config, f)
+    }
+
+    /// Converts the rows of `table` one by one, passing each member to `f`.
+    /// Returns the number of members.
+    fn for_each_in_table(
+        table: &Table,
+        config: &ParseConfig,
+        mut f: impl FnMut(Self) -> Result<(), QqExtractError>,
+    ) -> Result<usize, QqExtractError> {
         trace!("Table headers: {:?}", table.headers());
 
         // info!("Table {table:?}");
 
-        let members: Vec<Member> = table
+        for (i, row) in table.iter().enumerate() {
+            f(Member::from_row(i, row, config)?)?;
+        }
+
+        Ok(table.row_count())
+    }
+
+    /// Converts a row of the member table, along with its index for error
+    /// messages.
+    #[instrument(level = "debug", name = "row", skip(row, config))]
+    fn from_row(i: usize, row: Row<'_>, config: &ParseConfig) -> Result<Self, QqExtractError> {
+        debug!("Row: {:#?}", &row);
+        /*
+         Example:
+                 cells: [
+                    "",
+                    "1",
+                    "<a class=\"group-master-a\"><i class=\"icon-group-master\"></i></a>\n\n                <img class=\"\" id=\"useIcon1452313818\" src=\"//q4.qlogo.cn/g?b=qq&amp;nk=1452313818&amp;s=140\">\n\n                <span> 秘书组 </span>",
+                    "<span class=\"white\"> </span>",
+                    "1452313818",
+                    "男",
+                    "11年",
+                    "2018/02/26",
+                    "2021/11/01",
+                    "",
+                ]
+        */
+
+        let cell = |header: &'static str, cell_index: usize| {
+            row.get_by_index(cell_index)
+                .ok_or(QqExtractError::MissingCell { row: i, header })
+        };
+
+        let name_cell = Html::parse_fragment(cell("成员", config.name_col)?);
+
+        Ok(Member {
+            index: row
+                .get_by_index(config.index_col)
+                .and_then(|s| s.trim().parse().ok()),
+            qq_name: name_cell
+                .select(&QQ_NAME_SLT)
+                .next()
+                .ok_or_else(|| {
+                    QqExtractError::ParseError(format!("failed to find `成员` txt for elem {i}"))
+                })?
+                .text()
+                .collect::<String>()
+                .trim()
+                .to_owned(),
+            avatar_url: avatar_url_from_cell(&name_cell),
+            role: role_from_cell(&name_cell),
+            // members without a nickname get `<span class="white"> </span>`,
+            // which is read as an empty string
+            group_name: cell("群昵称", config.group_name_col)?.to_owned(),
+            qq_number: validate_qq_number(cell("QQ号", config.qq_number_col)?.trim())
+                .map_err(|e| QqExtractError::InRow {
+                    row: i,
+                    source: Box::new(e),
+                })?
+                .to_owned(),
+            gender: match cell("性别", config.gender_col)? {
+                "男" => Gender::Male,
+                "女" => Gender::Female,
+                "未知" => Gender::Unknown,
+                other => return Err(QqExtractError::InvalidGender(other.to_owned())),
+            },
+            qq_age: cell("Q龄", config.qq_age_col)?.to_owned(),
+            joined_date: parse_qq_date(cell("入群时间", config.joined_date_col)?),
+            last_spoken_date: parse_qq_date(cell("最后发言", config.last_spoken_col)?),
+        })
+    }
+
+    /// The header of the csv written by [`Member::parse_html_into`], in the
+    /// same order as [`Member::to_csv_record`].
+    pub fn csv_header() -> [&'static str; 7] {
+        ColumnNames::ZH.to_array()
+    }
+
+    /// The fields of the member as a csv row, under [`Member::csv_header`].
+    pub fn to_csv_record(&self) -> [String; 7] {
+        [
+            self.qq_name.clone(),
+            self.group_name.clone(),
+            self.qq_number.clone(),
+            self.gender.to_string(),
+            self.qq_age.clone(),
+            format_qq_date(self.joined_date),
+            format_qq_date(self.last_spoken_date),
+        ]
+    }
+
+    /// The fields of the member by their names in [`ColumnNames::EN`], e.g.
+    /// `display_name`, formatted as in [`Member::to_csv_record`], along with
+    /// `profile_url`, `avatar_url` (empty if unknown) and `role`.
+    ///
+    /// This is meant for template engines, which look values up by name.
+    pub fn to_hashmap(&self) -> HashMap<&'static str, String> {
+        let mut map: HashMap<_, _> = ColumnNames::EN
+            .to_array()
             .into_iter()
-            .enumerate()
-            .map(|(i, row)| {
-                debug!("Row: {:#?}", &row);
-                let cells: Vec<_> = row.iter().collect();
-
-                /*
-                 Example:
-                         cells: [
-                            "",
-                            "1",
-                            "<a class=\"group-master-a\"><i class=\"icon-group-master\"></i></a>\n\n                <img class=\"\" id=\"useIcon1452313818\" src=\"//q4.qlogo.cn/g?b=qq&amp;nk=1452313818&amp;s=140\">\n\n                <span> 秘书组 </span>",
-                            "<span class=\"white\"> </span>",
-                            "1452313818",
-                            "男",
-                            "11年",
-                            "2018/02/26",
-                            "2021/11/01",
-                            "",
-                        ]
-                */
-
-                Ok(Member {
-                    qq_name: {
-                        let name_raw_html = get_header(&cells, "成员", i, 2)?;
-                        Html::parse_fragment(name_raw_html)
-                            .select(&QQ_NAME_SLT)
-                            .next()
-                            .ok_or_else(|| {
-                                eyre!(format!("Failed to find `成员` txt for elem {i}"))
-                            })?
-                            .inner_html()
-                            .trim()
-                            .to_owned()
-                    },
-                    group_name: {
-                        let group_name_txt = get_header(&cells, "群昵称", i, 3)?;
-                        let group_name = Html::parse_fragment(group_name_txt)
-                            .select(&GROUP_NAME_SLT)
-                            .next()
-                            .ok_or_else(|| eyre!(format!("Failed to find `群昵称` for elem {i}")))?
-                            .inner_html()
-                            .trim()
-                            .to_owned();
-
-                        // if still has html, parse again
-                        if group_name.starts_with('<') {
-                            Html::parse_fragment(&group_name)
-                                .select(&GROUP_NAME_SLT)
-                                .next()
-                                .ok_or_else(|| {
-                                    eyre!(format!("Failed to find `群昵称` for elem {i}"))
-                                })?
-                                .inner_html()
-                                .trim()
-                                .to_owned()
-                        } else {
-                            group_name
-                        }
-                    },
-                    qq_number: get_header(&cells, "QQ号", i, 4)?.to_owned(),
-                    gender: match get_header(&cells, "性别", i, 5)?.as_str() {
-                        "男" => Gender::Male,
-                        "女" => Gender::Female,
-                        "未知" => Gender::Unknown,
-                        _ => panic!("Unrecognized Gender"),
-                    },
-                    qq_age: get_header(&cells, "Q龄", i, 6)?.to_owned(),
-                    joined_date: get_header(&cells, "入群时间", i, 7)?.to_owned(),
-                    last_spoken_date: get_header(&cells, "最后发言", i, 8)?.to_owned(),
-                })
+            .zip(self.to_csv_record())
+            .collect();
+        map.insert("profile_url", self.profile_url());
+        map.insert("avatar_url", self.avatar_url.clone().unwrap_or_default());
+        map.insert("role", self.role.to_string());
+        map
+    }
+
+    /// Parses a row of a csv written by this crate back into a member, looking
+    /// up the fields of [`Member::csv_header`] by name in `headers`.
+    ///
+    /// `成员` and `QQ号` are required. Other missing fields are left empty or
+    /// unknown. The `序号`, `头像链接` and `身份` columns, which are only written
+    /// when asked for, are read too if present.
+    pub fn from_csv_record(
+        record: &csv::StringRecord,
+        headers: &csv::StringRecord,
+    ) -> Result<Self, QqExtractError> {
+        let field = |header: &str| {
+            headers
+                .iter()
+                .position(|h| h.trim_start_matches('\u{feff}') == header)
+                .and_then(|i| record.get(i))
+        };
+        let required = |header: &'static str| {
+            field(header).ok_or_else(|| QqExtractError::MissingCell {
+                row: record.position().map_or(0, |p| p.record() as usize),
+                header,
             })
-            .collect::<Result<Vec<_>>>()
-            .wrap_err("Failed to parse members")?;
+        };
 
-        Ok(members)
+        Ok(Member {
+            index: field("序号").and_then(|s| s.trim().parse().ok()),
+            qq_name: required("成员")?.to_owned(),
+            group_name: field("群昵称").unwrap_or_default().to_owned(),
+            qq_number: required("QQ号")?.to_owned(),
+            gender: match field("性别").unwrap_or("未知") {
+                "男" => Gender::Male,
+                "女" => Gender::Female,
+                "未知" => Gender::Unknown,
+                other => return Err(QqExtractError::InvalidGender(other.to_owned())),
+            },
+            qq_age: field("Q龄").unwrap_or_default().to_owned(),
+            joined_date: field("入群时间").and_then(parse_qq_date),
+            last_spoken_date: field("最后发言").and_then(parse_qq_date),
+            avatar_url: field("头像链接")
+                .filter(|url| !url.is_empty())
+                .map(str::to_owned),
+            role: match field("身份").unwrap_or("成员") {
+                "群主" => Role::Owner,
+                "管理员" => Role::Admin,
+                "成员" => Role::Member,
+                other => {
+                    return Err(QqExtractError::ParseError(format!(
+                        "unrecognized role `{other}`"
+                    )))
+                }
+            },
+        })
+    }
+}
+
+/// Converts a row of the member table, along with its index for error messages.
+///
+/// The columns are taken from [`ParseConfig::DEFAULT`].
+impl TryFrom<(usize, Row<'_>)> for Member {
+    type Error = QqExtractError;
+
+    fn try_from((i, row): (usize, Row<'_>)) -> Result<Self, QqExtractError> {
+        Member::from_row(i, row, &ParseConfig::DEFAULT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The owner from the example row in [`Member::from_row`].
+    fn owner() -> Member {
+        Member {
+            index: Some(1),
+            qq_name: "秘书组".to_owned(),
+            group_name: String::new(),
+            qq_number: "1452313818".to_owned(),
+            gender: Gender::Male,
+            qq_age: "11年".to_owned(),
+            joined_date: NaiveDate::from_ymd_opt(2018, 2, 26),
+            last_spoken_date: NaiveDate::from_ymd_opt(2021, 11, 1),
+            avatar_url: None,
+            role: Role::Owner,
+        }
+    }
+
+    #[test]
+    fn modifying_a_clone_leaves_the_original() {
+        let original = owner();
+        let mut clone = original.clone();
+        assert_eq!(clone, original);
+
+        clone.group_name = "秘书".to_owned();
+        clone.gender = Gender::Female;
+        assert_ne!(clone, original);
+        assert_eq!(original.group_name, "");
+        assert_eq!(original.gender, Gender::Male);
+    }
+
+    #[test]
+    fn parses_qq_dates() {
+        assert_eq!(parse_qq_date("未知"), None);
+        assert_eq!(parse_qq_date(""), None);
+        assert_eq!(
+            parse_qq_date("2021/11/01"),
+            NaiveDate::from_ymd_opt(2021, 11, 1)
+        );
+    }
+
+    /// A member table with one row, whose `成员` and `群昵称` cells hold
+    /// `name_cell` and `group_name_cell`.
+    fn one_row(name_cell: &str, group_name_cell: &str) -> String {
+        format!(
+            r#"<table id="groupMember">
+<tr><th></th><th>序号</th><th>成员</th><th>群昵称</th><th>QQ号</th><th>性别</th><th>Q龄</th><th>入群时间</th><th>最后发言</th><th></th></tr>
+<tr class="mb"><td></td><td>1</td><td>{name_cell}</td><td>{group_name_cell}</td><td>10001</td><td>男</td><td>1年</td><td>2021/11/01</td><td>2021/11/01</td><td></td></tr>
+</table>"#
+        )
+    }
+
+    #[test]
+    fn whitespace_only_group_name_is_empty() {
+        for cell in [
+            r#"<span class="white"> </span>"#,
+            "<span class=\"white\">\n\t </span>",
+            "",
+        ] {
+            let members = Member::from_html(&one_row("<span>A</span>", cell)).unwrap();
+            assert_eq!(members[0].group_name, "");
+        }
+    }
+
+    #[test]
+    fn extracts_avatar_url_from_member_cell() {
+        // the `成员` cell of the example row in `Member::from_row`
+        let cell = Html::parse_fragment(
+            r#"<a class="group-master-a"><i class="icon-group-master"></i></a>
+
+                <img class="" id="useIcon1452313818" src="//q4.qlogo.cn/g?b=qq&amp;nk=1452313818&amp;s=140">
+
+                <span> 秘书组 </span>"#,
+        );
+        assert_eq!(
+            avatar_url_from_cell(&cell).as_deref(),
+            Some("https://q4.qlogo.cn/g?b=qq&nk=1452313818&s=140")
+        );
+
+        let no_avatar = Html::parse_fragment("<span> 秘书组 </span>");
+        assert_eq!(avatar_url_from_cell(&no_avatar), None);
+    }
+
+    #[test]
+    fn decodes_entities_in_names() {
+        let html = one_row(
+            "<span>A&lt;B</span>",
+            r#"<span class="white"><span>C&amp;D&gt;</span></span>"#,
+        );
+        let members = Member::from_html(&html).unwrap();
+        assert_eq!(members[0].qq_name, "A<B");
+        assert_eq!(members[0].group_name, "C&D>");
     }
 }
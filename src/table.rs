@@ -3,10 +3,11 @@
 //! Utility for extracting data from HTML tables.
 //!
 //! This library allows you to parse tables from HTML documents and iterate over
-//! their rows. There are three entry points:
+//! their rows. There are four entry points:
 //!
 //! - [`Table::find_first`] finds the first table.
 //! - [`Table::find_by_id`] finds a table by its HTML id.
+//! - [`Table::find_by_any_id`] finds a table by the first of several HTML ids.
 //! - [`Table::find_by_headers`] finds a table that has certain headers.
 //!
 //! Each of these returns an `Option<`[`Table`]`>`, since there might not be any
@@ -25,7 +26,7 @@
 //!         <tr><td>John</td><td>20</td></tr>
 //!     </table>
 //! "#;
-//! let table = table_extract::Table::find_first(html).unwrap();
+//! let table = qq_group_name_extract::table::Table::find_first(html).unwrap();
 //! for row in &table {
 //!     println!(
 //!         "{} is {} years old",
@@ -46,7 +47,7 @@
 //!         <tr><td>John</td><td>20</td></tr>
 //!     </table>
 //! "#;
-//! let table = table_extract::Table::find_by_headers(html, &["Age"]).unwrap();
+//! let table = qq_group_name_extract::table::Table::find_by_headers(html, &["Age"]).unwrap();
 //! for row in &table {
 //!     for cell in row {
 //!         println!("Table cell: {}", cell);
@@ -58,11 +59,16 @@
 //! [`Row`]: struct.Row.html
 //! [`Table::find_first`]: struct.Table.html#method.find_first
 //! [`Table::find_by_id`]: struct.Table.html#method.find_by_id
+//! [`Table::find_by_any_id`]: struct.Table.html#method.find_by_any_id
 //! [`Table::find_by_headers`]: struct.Table.html#method.find_by_headers
 
 use scraper::element_ref::ElementRef;
 use scraper::{Html, Selector};
+use serde::Serialize;
 use std::collections::HashMap;
+use std::fmt;
+use std::ops::{Deref, Index};
+use tracing::debug;
 
 /// A map from `<th>` table headers to their zero-based positions.
 ///
@@ -76,12 +82,100 @@ use std::collections::HashMap;
 /// ```
 ///
 /// The `Headers` for this table would map "Name" to 0 and "Age" to 1.
-pub type Headers = HashMap<String, usize>;
+///
+/// `Headers` dereferences to the underlying `HashMap`, and adds lookups in the
+/// other direction, from positions to names.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct Headers {
+    map: HashMap<String, usize>,
+    /// The header of each column by position, empty for columns without one.
+    #[serde(skip)]
+    names: Vec<String>,
+}
+
+impl Headers {
+    /// Returns the name of the header at the zero-based position `index`.
+    pub fn index_to_name(&self, index: usize) -> Option<&str> {
+        self.names
+            .get(index)
+            .map(String::as_str)
+            .filter(|name| !name.is_empty())
+    }
+
+    /// Returns the header names by their position in the table, up to the last
+    /// header. Columns without a header of their own, such as those covered by
+    /// a `colspan`, have an empty name.
+    pub fn names_in_order(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Adds the header `name` at the zero-based position `index`.
+    fn insert(&mut self, name: String, index: usize) {
+        if self.names.len() <= index {
+            self.names.resize(index + 1, String::new());
+        }
+        self.names[index].clone_from(&name);
+        self.map.insert(name, index);
+    }
+}
+
+impl Deref for Headers {
+    type Target = HashMap<String, usize>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.map
+    }
+}
+
+impl From<HashMap<String, usize>> for Headers {
+    fn from(map: HashMap<String, usize>) -> Self {
+        map.into_iter().collect()
+    }
+}
+
+impl FromIterator<(String, usize)> for Headers {
+    fn from_iter<I: IntoIterator<Item = (String, usize)>>(iter: I) -> Self {
+        let mut headers = Headers::default();
+        for (name, index) in iter {
+            headers.insert(name, index);
+        }
+        headers
+    }
+}
+
+/// How the contents of each cell are read by [`Table::with_config`].
+///
+/// [`Table::with_config`]: struct.Table.html#method.with_config
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum CellMode {
+    /// The inner HTML of the cell, markup and all. This is what [`Table::new`]
+    /// uses.
+    ///
+    /// [`Table::new`]: struct.Table.html#method.new
+    #[default]
+    InnerHtml,
+    /// Only the text of the cell, with all of its text nodes concatenated and
+    /// entities decoded.
+    TextOnly,
+}
+
+/// Options for parsing a table with [`Table::with_config`].
+///
+/// [`Table::with_config`]: struct.Table.html#method.with_config
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct TableConfig {
+    /// How the contents of header and data cells are read.
+    pub cell_mode: CellMode,
+    /// A column whose data cells keep their inner HTML whatever `cell_mode`
+    /// says, for tables where only one column needs its markup.
+    pub inner_html_column: Option<usize>,
+}
 
 /// A parsed HTML table.
 ///
 /// See [the module level documentation](index.html) for more.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
 pub struct Table {
     headers: Headers,
     data: Vec<Vec<String>>,
@@ -94,6 +188,40 @@ impl Table {
         html.select(&css("table")).next().map(Table::new)
     }
 
+    /// Finds the first table in `html`, falling back to the first ARIA table
+    /// (`<div role="table">`) if there is no `<table>` element.
+    ///
+    /// For ARIA tables, `div[role="row"]` elements are rows, and
+    /// `div[role="columnheader"]` and `div[role="cell"]` elements play the part
+    /// of `<th>` and `<td>`.
+    pub fn find_first_or_aria(html: &str) -> Option<Table> {
+        let html = Html::parse_fragment(html);
+        html.select(&css("table"))
+            .next()
+            .map(Table::new)
+            .or_else(|| {
+                html.select(&css(r#"div[role="table"]"#))
+                    .next()
+                    .map(|element| {
+                        Table::from_parts(
+                            element,
+                            &css(r#"div[role="row"]"#),
+                            &css(r#"div[role="columnheader"]"#),
+                            &css(r#"div[role="cell"]"#),
+                            &TableConfig::default(),
+                        )
+                    })
+            })
+    }
+
+    /// Finds the `n`th (zero-based) table in `html`.
+    ///
+    /// Returns `None` if there are `n` or fewer tables.
+    pub fn nth(html: &str, n: usize) -> Option<Table> {
+        let html = Html::parse_fragment(html);
+        html.select(&css("table")).nth(n).map(Table::new)
+    }
+
     /// Finds the table in `html` with an id of `id`.
     pub fn find_by_id(html: &str, id: &str) -> Option<Table> {
         let html = Html::parse_fragment(html);
@@ -106,6 +234,43 @@ impl Table {
             .map(Table::new)
     }
 
+    /// Finds the first table in `html` whose id is one of `ids`, trying each id
+    /// in order.
+    ///
+    /// Returns the table together with the id that matched, so callers can tell
+    /// which version of a page they are looking at.
+    pub fn find_by_any_id<'a>(html: &str, ids: &[&'a str]) -> Option<(Table, &'a str)> {
+        let html = Html::parse_fragment(html);
+        ids.iter().find_map(|&id| {
+            Selector::parse(&format!("table#{}", id))
+                .ok()
+                .and_then(|s| html.select(&s).next())
+                .map(|table| (Table::new(table), id))
+        })
+    }
+
+    /// Finds the first table in `html` whose `attr` attribute is exactly
+    /// `value`, e.g. `data-group-id="12345"`.
+    ///
+    /// This follows CSS attribute selector semantics, so the whole value must
+    /// match. Returns `None` if `attr` or `value` contain any of `[`, `]`, `"`,
+    /// `'` or `\`, since they could change the meaning of the selector.
+    pub fn find_by_attribute(html: &str, attr: &str, value: &str) -> Option<Table> {
+        const FORBIDDEN: &[char] = &['[', ']', '"', '\'', '\\'];
+        if attr.contains(FORBIDDEN) || value.contains(FORBIDDEN) {
+            return None;
+        }
+
+        let html = Html::parse_fragment(html);
+        let selector = format!(r#"table[{}="{}"]"#, attr, value);
+        Selector::parse(&selector)
+            .ok()
+            .as_ref()
+            .map(|s| html.select(s))
+            .and_then(|mut s| s.next())
+            .map(Table::new)
+    }
+
     /// Finds the table in `html` whose first row contains all of the headers
     /// specified in `headers`. The order does not matter.
     ///
@@ -124,58 +289,423 @@ impl Table {
         let sel_th = css("th");
 
         let html = Html::parse_fragment(html);
-        html.select(&sel_table)
-            .find(|table| {
-                table.select(&sel_tr).next().map_or(false, |tr| {
-                    let cells = select_cells(tr, &sel_th);
-                    headers.iter().all(|h| contains_str(&cells, h.as_ref()))
-                })
+        let mut scanned = 0;
+        let found = html.select(&sel_table).enumerate().find(|(_, table)| {
+            scanned += 1;
+            table.select(&sel_tr).next().is_some_and(|tr| {
+                let cells = select_cells(tr, &sel_th);
+                headers.iter().all(|h| contains_str(&cells, h.as_ref()))
             })
-            .map(Table::new)
+        });
+        match found {
+            Some((i, _)) => debug!("Scanned {} tables, found match at index {}", scanned, i),
+            None => debug!("Scanned {} tables, found no match", scanned),
+        }
+        found.map(|(_, table)| Table::new(table))
+    }
+
+    /// Like [`find_by_headers`](#method.find_by_headers), but `headers` is a
+    /// list of `(required_name, normalized_name)` pairs, and each required
+    /// header is renamed to its normalized name in the returned table.
+    ///
+    /// Headers that aren't listed keep their names from the HTML. Returns
+    /// `None` if two headers would end up with the same name, since one of
+    /// them couldn't be looked up any more.
+    pub fn find_by_headers_normalized(html: &str, headers: &[(&str, &str)]) -> Option<Table> {
+        let required: Vec<_> = headers.iter().map(|&(required, _)| required).collect();
+        let mut table = Table::find_by_headers(html, &required)?;
+
+        // take all of them out first, so that headers can swap names
+        let mut renamed = Vec::new();
+        for &(required, normalized) in headers {
+            if let Some(i) = table.headers.map.remove(required) {
+                renamed.push((normalized, i));
+            }
+        }
+        for (normalized, i) in renamed {
+            if table.headers.contains_key(normalized) {
+                debug!("More than one header normalizes to {:?}", normalized);
+                return None;
+            }
+            table.headers.insert(normalized.to_string(), i);
+        }
+        Some(table)
+    }
+
+    /// Builds a table from `headers` and the cells of each row, without any
+    /// HTML.
+    pub fn from_data(headers: Headers, data: Vec<Vec<String>>) -> Table {
+        Table { headers, data }
     }
 
     /// Returns the headers of the table.
     ///
     /// This will be empty if the table had no `<th>` tags in its first row. See
-    /// [`Headers`](type.Headers.html) for more.
+    /// [`Headers`](struct.Headers.html) for more.
     pub fn headers(&self) -> &Headers {
         &self.headers
     }
 
+    /// Returns the header names with their zero-based positions, sorted by
+    /// position, i.e. in the order the columns appear in the table. Columns
+    /// without a header are left out, unlike in [`Headers::names_in_order`].
+    pub fn headers_in_order(&self) -> Vec<(&str, usize)> {
+        self.headers
+            .names_in_order()
+            .iter()
+            .enumerate()
+            .filter(|(_, name)| !name.is_empty())
+            .map(|(i, name)| (name.as_str(), i))
+            .collect()
+    }
+
+    /// Returns the number of rows in the table, not counting the header row.
+    pub fn row_count(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns the cell at zero-based `row` and `col`, or `None` if it is out
+    /// of bounds.
+    ///
+    /// Like [`iter`](#method.iter), rows are counted after the header row.
+    pub fn get(&self, row: usize, col: usize) -> Option<&str> {
+        self.data
+            .get(row)
+            .and_then(|cells| cells.get(col))
+            .map(String::as_str)
+    }
+
+    /// Returns the number of columns in the table.
+    ///
+    /// This is the number of headers or the length of the longest row,
+    /// whichever is greater.
+    pub fn column_count(&self) -> usize {
+        self.headers
+            .len()
+            .max(self.data.iter().map(|r| r.len()).max().unwrap_or(0))
+    }
+
+    /// Merges two tables side by side, with the columns of `other` after the
+    /// columns of `self`.
+    ///
+    /// Both tables must have the same number of rows, and no header may appear
+    /// in both. Rows of `self` shorter than its
+    /// [`column_count`](#method.column_count) are padded with empty cells so
+    /// that the columns of `other` stay aligned.
+    pub fn zip(self, other: Table) -> Result<Table, TableZipError> {
+        if self.row_count() != other.row_count() {
+            return Err(TableZipError::RowCountMismatch {
+                left: self.row_count(),
+                right: other.row_count(),
+            });
+        }
+        if let Some(header) = other.headers.keys().find(|h| self.headers.contains_key(*h)) {
+            return Err(TableZipError::DuplicateHeader(header.clone()));
+        }
+
+        let width = self.column_count();
+        let mut headers = self.headers;
+        for (h, i) in other.headers.map {
+            headers.insert(h, i + width);
+        }
+        let data = self
+            .data
+            .into_iter()
+            .zip(other.data)
+            .map(|(mut left, right)| {
+                left.resize(width, String::new());
+                left.extend(right);
+                left
+            })
+            .collect();
+
+        Ok(Table { headers, data })
+    }
+
+    /// Appends the rows of `other` to the rows of `self`.
+    ///
+    /// Both tables must have exactly the same headers, at the same positions.
+    pub fn merge(mut self, other: Table) -> Result<Table, TableMergeError> {
+        if self.headers != other.headers {
+            let mut error = TableMergeError::default();
+            for (header, i) in self.headers.iter() {
+                match other.headers.get(header) {
+                    None => error.missing.push(header.clone()),
+                    Some(j) if j != i => error.mismatched.push(header.clone()),
+                    Some(_) => {}
+                }
+            }
+            error.unexpected = other
+                .headers
+                .keys()
+                .filter(|h| !self.headers.contains_key(*h))
+                .cloned()
+                .collect();
+            error.missing.sort();
+            error.unexpected.sort();
+            error.mismatched.sort();
+            return Err(error);
+        }
+
+        self.data.extend(other.data);
+        Ok(self)
+    }
+
+    /// Returns a new table with the same headers and only the rows for which
+    /// `predicate` returns `true`.
+    pub fn filter_rows<F>(&self, predicate: F) -> Table
+    where
+        F: Fn(&Row) -> bool,
+    {
+        let data = self
+            .iter()
+            .filter(|row| predicate(row))
+            .map(|row| row.cells.to_vec())
+            .collect();
+
+        Table {
+            headers: self.headers.clone(),
+            data,
+        }
+    }
+
+    /// Returns a new table with only the columns named in `names`, in that
+    /// order.
+    ///
+    /// Names that aren't headers of this table are skipped. Rows too short to
+    /// have a selected cell get an empty one instead.
+    pub fn select_columns(&self, names: &[&str]) -> Table {
+        let indices: Vec<_> = names
+            .iter()
+            .filter_map(|&name| self.headers.get(name).map(|&i| (name, i)))
+            .collect();
+
+        let headers = indices
+            .iter()
+            .enumerate()
+            .map(|(new_i, &(name, _))| (name.to_string(), new_i))
+            .collect();
+        let data = self
+            .data
+            .iter()
+            .map(|row| {
+                indices
+                    .iter()
+                    .map(|&(_, i)| row.get(i).cloned().unwrap_or_default())
+                    .collect()
+            })
+            .collect();
+
+        Table { headers, data }
+    }
+
+    /// Returns a new table with the same headers and the rows in
+    /// `start..end`.
+    ///
+    /// The table is empty if `start >= end`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `end` is greater than the [`row_count`](#method.row_count),
+    /// just like slicing a `Vec`. See [`try_window`](#method.try_window) for a
+    /// non-panicking version.
+    pub fn window(&self, start: usize, end: usize) -> Table {
+        self.try_window(start, end).unwrap_or_else(|| {
+            panic!(
+                "window end {} out of range for table of {} rows",
+                end,
+                self.row_count()
+            )
+        })
+    }
+
+    /// Like [`window`](#method.window), but returns `None` instead of panicking
+    /// if `end` is out of range.
+    pub fn try_window(&self, start: usize, end: usize) -> Option<Table> {
+        let data = if start >= end {
+            Vec::new()
+        } else {
+            self.data.get(start..end)?.to_vec()
+        };
+
+        Some(Table {
+            headers: self.headers.clone(),
+            data,
+        })
+    }
+
     /// Returns an iterator over the [`Row`](struct.Row.html)s of the table.
     ///
     /// Only `<td>` cells are considered when generating rows. If the first row
     /// of the table is a header row, meaning it contains at least one `<th>`
     /// cell, the iterator will start on the second row. Use
     /// [`headers`](#method.headers) to access the header row in that case.
-    pub fn iter(&self) -> Iter {
+    pub fn iter(&self) -> Iter<'_> {
         Iter {
             headers: &self.headers,
             iter: self.data.iter(),
         }
     }
 
+    /// Serializes the table to a pretty-printed
+    /// [RON](https://github.com/ron-rs/ron) string.
+    #[cfg(feature = "ron")]
+    pub fn to_ron_string(&self) -> Result<String, ron::Error> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+    }
+
+    /// Parses the `<table>` `element`.
+    ///
+    /// If the table has a `<thead>`, the headers are taken from its last row
+    /// (earlier rows usually group the columns), and the rows from the
+    /// `<tbody>`s. Otherwise the first row is the header row if it has any
+    /// `<th>` cells. Rows in a `<tfoot>` are summaries, not data, and are left
+    /// out either way.
+    ///
+    /// Cells hold their inner HTML; use [`with_config`](#method.with_config)
+    /// to read only their text instead.
     pub fn new(element: ElementRef) -> Table {
+        Table::with_config(element, &TableConfig::default())
+    }
+
+    /// Parses the `<table>` `element` like [`new`](#method.new), reading the
+    /// cells as `config` says.
+    pub fn with_config(element: ElementRef, config: &TableConfig) -> Table {
         let sel_tr = css("tr");
         let sel_th = css("th");
         let sel_td = css("td");
+        let mode = config.cell_mode;
 
-        let mut headers = HashMap::new();
-        let mut rows = element.select(&sel_tr).peekable();
-        if let Some(tr) = rows.peek() {
-            for (i, th) in tr.select(&sel_th).enumerate() {
-                headers.insert(cell_content(th), i);
-            }
+        if let Some(thead) = child_elements(element, "thead").next() {
+            let headers = child_elements(thead, "tr")
+                .last()
+                .map(|tr| select_headers(tr, &sel_th, mode))
+                .unwrap_or_default();
+            let rows =
+                child_elements(element, "tbody").flat_map(|tbody| child_elements(tbody, "tr"));
+            return Table::from_rows(headers, rows, &sel_td, config);
         }
+
+        let rows = element.select(&sel_tr).filter(|tr| !in_tfoot(*tr));
+        Table::from_first_row_headers(rows, &sel_th, &sel_td, config)
+    }
+
+    fn from_parts(
+        element: ElementRef,
+        sel_tr: &Selector,
+        sel_th: &Selector,
+        sel_td: &Selector,
+        config: &TableConfig,
+    ) -> Table {
+        Table::from_first_row_headers(element.select(sel_tr), sel_th, sel_td, config)
+    }
+
+    /// Builds a table from `rows`, the first of which is the header row if it
+    /// has any `sel_th` cells.
+    fn from_first_row_headers<'a>(
+        rows: impl Iterator<Item = ElementRef<'a>>,
+        sel_th: &Selector,
+        sel_td: &Selector,
+        config: &TableConfig,
+    ) -> Table {
+        let mut rows = rows.peekable();
+        let headers = rows
+            .peek()
+            .map(|&tr| select_headers(tr, sel_th, config.cell_mode))
+            .unwrap_or_default();
         if !headers.is_empty() {
             rows.next();
         }
-        let data = rows.map(|tr| select_cells(tr, &sel_td)).collect();
+
+        Table::from_rows(headers, rows, sel_td, config)
+    }
+
+    fn from_rows<'a>(
+        headers: Headers,
+        rows: impl Iterator<Item = ElementRef<'a>>,
+        sel_td: &Selector,
+        config: &TableConfig,
+    ) -> Table {
+        let mut rowspans = HashMap::new();
+        let data = rows
+            .map(|tr| select_spanned_cells(tr, sel_td, config, &mut rowspans))
+            .collect();
 
         Table { headers, data }
     }
 }
 
+/// The error returned by [`Table::zip`](struct.Table.html#method.zip).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TableZipError {
+    /// The tables have different numbers of rows.
+    RowCountMismatch { left: usize, right: usize },
+    /// A header appears in both tables.
+    DuplicateHeader(String),
+}
+
+impl fmt::Display for TableZipError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TableZipError::RowCountMismatch { left, right } => write!(
+                f,
+                "cannot zip a table of {} rows with a table of {} rows",
+                left, right
+            ),
+            TableZipError::DuplicateHeader(header) => {
+                write!(f, "header `{}` appears in both tables", header)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TableZipError {}
+
+/// The error returned by [`Table::merge`](struct.Table.html#method.merge) when
+/// the headers of the tables differ.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TableMergeError {
+    /// Headers of the first table that the second table doesn't have.
+    pub missing: Vec<String>,
+    /// Headers of the second table that the first table doesn't have.
+    pub unexpected: Vec<String>,
+    /// Headers of both tables, but at different positions.
+    pub mismatched: Vec<String>,
+}
+
+impl fmt::Display for TableMergeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cannot merge tables with different headers")?;
+        for (what, headers) in [
+            ("missing", &self.missing),
+            ("unexpected", &self.unexpected),
+            ("at different positions", &self.mismatched),
+        ] {
+            if !headers.is_empty() {
+                write!(f, "; {}: {}", what, headers.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for TableMergeError {}
+
+/// Indexes the table by `(row, column)`, like [`Table::get`].
+///
+/// # Panics
+///
+/// Panics if the cell is out of bounds.
+///
+/// [`Table::get`]: struct.Table.html#method.get
+impl Index<(usize, usize)> for Table {
+    type Output = str;
+
+    fn index(&self, (row, col): (usize, usize)) -> &str {
+        &self.data[row][col]
+    }
+}
+
 impl<'a> IntoIterator for &'a Table {
     type Item = Row<'a>;
     type IntoIter = Iter<'a>;
@@ -198,6 +728,23 @@ impl<'a> Iterator for Iter<'a> {
         let headers = self.headers;
         self.iter.next().map(|cells| Row { headers, cells })
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a> DoubleEndedIterator for Iter<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let headers = self.headers;
+        self.iter.next_back().map(|cells| Row { headers, cells })
+    }
+}
+
+impl<'a> ExactSizeIterator for Iter<'a> {
+    fn len(&self) -> usize {
+        self.iter.len()
+    }
 }
 
 /// A row in a [`Table`](struct.Table.html).
@@ -237,13 +784,45 @@ impl<'a> Row<'a> {
             .and_then(|&i| self.cells.get(i).map(String::as_str))
     }
 
+    /// Returns the cell at the zero-based position `index`.
+    ///
+    /// Returns `None` if the row has `index` or fewer cells.
+    pub fn get_by_index(&self, index: usize) -> Option<&'a str> {
+        self.cells.get(index).map(String::as_str)
+    }
+
+    /// Returns an owned map from each header to the cell underneath it.
+    ///
+    /// Headers without a cell in this row map to an empty string.
+    pub fn to_hashmap(&self) -> HashMap<String, String> {
+        self.headers
+            .iter()
+            .map(|(header, &i)| {
+                let cell = self.cells.get(i).cloned().unwrap_or_default();
+                (header.clone(), cell)
+            })
+            .collect()
+    }
+
+    /// Returns an iterator over `(header, cell)` pairs, in column order.
+    ///
+    /// Cells without a header are paired with `""`, and headers past the end of
+    /// the row are left out.
+    pub fn zip_with_headers(&self) -> impl Iterator<Item = (&'a str, &'a str)> {
+        let headers = self.headers;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(i, cell)| (headers.index_to_name(i).unwrap_or(""), cell.as_str()))
+    }
+
     /// Returns a slice containing all the cells.
     pub fn as_slice(&self) -> &'a [String] {
         self.cells
     }
 
     /// Returns an iterator over the cells of the row.
-    pub fn iter(&self) -> std::slice::Iter<String> {
+    pub fn iter(&self) -> std::slice::Iter<'_, String> {
         self.cells.iter()
     }
 }
@@ -262,13 +841,280 @@ fn css(selector: &'static str) -> Selector {
 }
 
 fn select_cells(element: ElementRef, selector: &Selector) -> Vec<String> {
-    element.select(selector).map(cell_content).collect()
+    element
+        .select(selector)
+        .map(|cell| cell_content(cell, CellMode::InnerHtml))
+        .collect()
+}
+
+fn select_headers(element: ElementRef, selector: &Selector, mode: CellMode) -> Headers {
+    let mut headers = Headers::default();
+    let mut i = 0;
+    for th in element.select(selector) {
+        headers.insert(cell_content(th, mode), i);
+        i += span(th, "colspan", MAX_COLSPAN);
+    }
+    headers
+}
+
+fn in_tfoot(tr: ElementRef) -> bool {
+    tr.parent()
+        .and_then(ElementRef::wrap)
+        .is_some_and(|parent| parent.value().name() == "tfoot")
 }
 
-fn cell_content(element: ElementRef) -> String {
-    element.inner_html().trim().to_string()
+/// Iterates over the child elements of `element` named `name`.
+fn child_elements<'a>(
+    element: ElementRef<'a>,
+    name: &'static str,
+) -> impl Iterator<Item = ElementRef<'a>> {
+    element
+        .children()
+        .filter_map(ElementRef::wrap)
+        .filter(move |e| e.value().name() == name)
+}
+
+// the limits browsers use
+const MAX_COLSPAN: usize = 1000;
+const MAX_ROWSPAN: usize = 65534;
+
+/// Like [`select_cells`], but a cell with a `colspan` or `rowspan` is followed
+/// by empty cells in the columns and rows it covers, so that every cell stays
+/// under its header.
+///
+/// `rowspans` maps columns to how many more rows are covered by a cell from an
+/// earlier row, and is updated for the next row.
+fn select_spanned_cells(
+    element: ElementRef,
+    selector: &Selector,
+    config: &TableConfig,
+    rowspans: &mut HashMap<usize, usize>,
+) -> Vec<String> {
+    let mut cells = Vec::new();
+    let mut new_rowspans = Vec::new();
+    for cell in element.select(selector) {
+        while rowspans.contains_key(&cells.len()) {
+            cells.push(String::new());
+        }
+
+        let col = cells.len();
+        let colspan = span(cell, "colspan", MAX_COLSPAN);
+        let rowspan = span(cell, "rowspan", MAX_ROWSPAN);
+        let mode = if config.inner_html_column == Some(col) {
+            CellMode::InnerHtml
+        } else {
+            config.cell_mode
+        };
+        cells.push(cell_content(cell, mode));
+        cells.resize(col + colspan, String::new());
+        if rowspan > 1 {
+            new_rowspans.extend((col..col + colspan).map(|c| (c, rowspan - 1)));
+        }
+    }
+    if let Some(&last) = rowspans.keys().max() {
+        if cells.len() <= last {
+            cells.resize(last + 1, String::new());
+        }
+    }
+
+    rowspans.retain(|_, rows| {
+        *rows -= 1;
+        *rows > 0
+    });
+    rowspans.extend(new_rowspans);
+    cells
+}
+
+/// Reads a `colspan` or `rowspan` attribute, which is 1 if missing or invalid.
+fn span(element: ElementRef, attr: &str, max: usize) -> usize {
+    element
+        .value()
+        .attr(attr)
+        .and_then(|v| v.trim().parse().ok())
+        .filter(|&n| n > 0)
+        .map_or(1, |n: usize| n.min(max))
+}
+
+fn cell_content(element: ElementRef, mode: CellMode) -> String {
+    match mode {
+        CellMode::InnerHtml => element.inner_html().trim().to_string(),
+        CellMode::TextOnly => element.text().collect::<String>().trim().to_string(),
+    }
 }
 
 fn contains_str(slice: &[String], item: &str) -> bool {
     slice.iter().any(|s| s == item)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(table: &Table) -> Vec<Vec<&str>> {
+        table
+            .iter()
+            .map(|row| row.as_slice().iter().map(String::as_str).collect())
+            .collect()
+    }
+
+    #[test]
+    fn colspan_is_followed_by_empty_cells() {
+        let html = r#"<table>
+<tr><th>身份</th><th>成员</th><th>QQ号</th></tr>
+<tr><td colspan="2">群主</td><td>10001</td></tr>
+<tr><td>成员</td><td>Tom</td><td>10002</td></tr>
+</table>"#;
+        let table = Table::find_first(html).unwrap();
+        assert_eq!(
+            rows(&table),
+            [["群主", "", "10001"], ["成员", "Tom", "10002"]]
+        );
+        assert_eq!(table.iter().next().unwrap().get("QQ号"), Some("10001"));
+    }
+
+    #[test]
+    fn headers_are_looked_up_by_position() {
+        let html = r#"<table><tr><th colspan="2">成员</th><th>QQ号</th></tr></table>"#;
+        let table = Table::find_first(html).unwrap();
+        let headers = table.headers();
+        assert_eq!(headers.names_in_order(), ["成员", "", "QQ号"]);
+        assert_eq!(headers.index_to_name(0), Some("成员"));
+        assert_eq!(headers.index_to_name(1), None);
+        assert_eq!(headers.index_to_name(2), Some("QQ号"));
+        assert_eq!(headers.index_to_name(3), None);
+    }
+
+    #[test]
+    fn rowspan_is_followed_by_empty_cells_below() {
+        let html = r#"<table>
+<tr><th>入群时间</th><th>QQ号</th></tr>
+<tr><td rowspan="3">2021/11/01</td><td>10001</td></tr>
+<tr><td>10002</td></tr>
+<tr><td>10003</td></tr>
+<tr><td>2022/01/05</td><td>10004</td></tr>
+</table>"#;
+        let table = Table::find_first(html).unwrap();
+        assert_eq!(
+            rows(&table),
+            [
+                ["2021/11/01", "10001"],
+                ["", "10002"],
+                ["", "10003"],
+                ["2022/01/05", "10004"],
+            ]
+        );
+    }
+
+    const NORMALIZED_TABLE: &str = r#"<table>
+<tr><th>成员</th><th>昵称</th><th>QQ号</th></tr>
+<tr><td>Tom</td><td>Tommy</td><td>10001</td></tr>
+</table>"#;
+
+    #[test]
+    fn headers_are_normalized() {
+        let table = Table::find_by_headers_normalized(
+            NORMALIZED_TABLE,
+            &[("成员", "name"), ("QQ号", "qq")],
+        )
+        .unwrap();
+        assert_eq!(
+            table.headers_in_order(),
+            [("name", 0), ("昵称", 1), ("qq", 2)]
+        );
+
+        // headers can swap names
+        let table = Table::find_by_headers_normalized(
+            NORMALIZED_TABLE,
+            &[("成员", "昵称"), ("昵称", "成员")],
+        )
+        .unwrap();
+        assert_eq!(
+            table.headers_in_order(),
+            [("昵称", 0), ("成员", 1), ("QQ号", 2)]
+        );
+    }
+
+    #[test]
+    fn headers_normalized_to_the_same_name_are_rejected() {
+        let both_to_name = [("成员", "name"), ("昵称", "name")];
+        assert!(Table::find_by_headers_normalized(NORMALIZED_TABLE, &both_to_name).is_none());
+
+        // or to the name of a header that isn't listed
+        let onto_unlisted = [("成员", "QQ号")];
+        assert!(Table::find_by_headers_normalized(NORMALIZED_TABLE, &onto_unlisted).is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "ron")]
+    fn serializes_to_ron() {
+        let html = r#"<table><tr><th>QQ号</th></tr><tr><td>10001</td></tr></table>"#;
+        let ron = Table::find_first(html).unwrap().to_ron_string().unwrap();
+        let value: ron::Value = ron::from_str(&ron).unwrap();
+        let expected: ron::Value =
+            ron::from_str(r#"(headers: {"QQ号": 0}, data: [["10001"]])"#).unwrap();
+        assert_eq!(value, expected);
+    }
+
+    const GROUP_TABLES: &str = r#"<table data-group-id="12345"><tr><th>QQ号</th></tr><tr><td>10001</td></tr></table>
+<table data-group-id="67890"><tr><th>QQ号</th></tr><tr><td>10002</td></tr></table>
+<table data-group-id="67890"><tr><th>QQ号</th></tr><tr><td>10003</td></tr></table>"#;
+
+    #[test]
+    fn finds_table_by_data_attribute() {
+        let table = Table::find_by_attribute(GROUP_TABLES, "data-group-id", "12345").unwrap();
+        assert_eq!(rows(&table), [["10001"]]);
+    }
+
+    #[test]
+    fn data_attribute_must_match_exactly() {
+        assert!(Table::find_by_attribute(GROUP_TABLES, "data-group-id", "1234").is_none());
+        assert!(Table::find_by_attribute(GROUP_TABLES, "data-group-id", "54321").is_none());
+        assert!(Table::find_by_attribute(GROUP_TABLES, "data-group", "12345").is_none());
+        assert!(Table::find_by_attribute(GROUP_TABLES, "data-group-id", r#"12345"]"#).is_none());
+    }
+
+    #[test]
+    fn first_of_several_matching_tables_is_found() {
+        let table = Table::find_by_attribute(GROUP_TABLES, "data-group-id", "67890").unwrap();
+        assert_eq!(rows(&table), [["10002"]]);
+    }
+
+    fn parse_with(html: &str, config: &TableConfig) -> Table {
+        let html = Html::parse_fragment(html);
+        let element = html.select(&css("table")).next().unwrap();
+        Table::with_config(element, config)
+    }
+
+    const MARKUP_TABLE: &str = r#"<table>
+<tr><th><b>成员</b></th><th>群昵称</th></tr>
+<tr><td><img src="a.png"><span>Tom</span></td><td><span>C&amp;D</span></td></tr>
+</table>"#;
+
+    #[test]
+    fn text_only_strips_markup_and_decodes_entities() {
+        let config = TableConfig {
+            cell_mode: CellMode::TextOnly,
+            ..TableConfig::default()
+        };
+        let table = parse_with(MARKUP_TABLE, &config);
+        assert_eq!(table.headers().names_in_order(), ["成员", "群昵称"]);
+        assert_eq!(rows(&table), [["Tom", "C&D"]]);
+    }
+
+    #[test]
+    fn inner_html_column_keeps_its_markup() {
+        let config = TableConfig {
+            cell_mode: CellMode::TextOnly,
+            inner_html_column: Some(0),
+        };
+        let table = parse_with(MARKUP_TABLE, &config);
+        assert_eq!(
+            rows(&table),
+            [[r#"<img src="a.png"><span>Tom</span>"#, "C&D"]]
+        );
+        assert_eq!(
+            rows(&parse_with(MARKUP_TABLE, &TableConfig::default())),
+            rows(&Table::find_first(MARKUP_TABLE).unwrap())
+        );
+    }
+}
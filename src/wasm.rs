@@ -0,0 +1,22 @@
+//! Bindings for javascript, built with `wasm-pack build --target web -- --features wasm`.
+//!
+//! ```js
+//! import init, { parse_html_to_json } from "./pkg/qq_group_name_extract.js";
+//!
+//! await init();
+//! const members = parse_html_to_json(document.documentElement.outerHTML);
+//! ```
+
+use wasm_bindgen::prelude::*;
+
+use crate::{error::display_chain, qqtable::Member};
+
+/// Parses the members in the html of `https://qun.qq.com/member.html` into an
+/// array of plain objects, with the same fields as [`Member`]'s json.
+///
+/// Throws the error message, with its causes, as a string if the member table can't be read.
+#[wasm_bindgen]
+pub fn parse_html_to_json(html: &str) -> Result<JsValue, JsValue> {
+    let members = Member::from_html(html).map_err(|err| JsValue::from_str(&display_chain(&err)))?;
+    serde_wasm_bindgen::to_value(&members).map_err(JsValue::from)
+}
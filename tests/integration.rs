@@ -0,0 +1,548 @@
+use std::{fs, path::PathBuf, process::Command};
+
+use chrono::NaiveDate;
+use qq_group_name_extract::{
+    error::QqExtractError,
+    filter::MemberFilter,
+    group::GroupExport,
+    qqtable::{Gender, Member, Role},
+};
+use regex::Regex;
+
+const SAMPLE_GROUP: &str = include_str!("fixtures/sample_group.html");
+
+/// An empty directory for `test` under cargo's scratch space for tests.
+fn scratch_dir(test: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(test);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn members_round_trip_through_csv() {
+    let members = GroupExport::from_html(SAMPLE_GROUP).unwrap().members;
+    assert_eq!(members.len(), 5);
+    assert_eq!(members[0].role, Role::Owner);
+    assert_eq!(members[1].role, Role::Admin);
+    assert_eq!(members[3].gender, Gender::Unknown);
+
+    // the columns of `--include-index --include-avatar-url --include-role`
+    // after the usual ones, so that every field is written
+    let extra_headers = ["序号", "头像链接", "身份"];
+    let mut wtr = csv::Writer::from_writer(Vec::new());
+    wtr.write_record(Member::csv_header().into_iter().chain(extra_headers))
+        .unwrap();
+    for member in &members {
+        let extra = [
+            member.index.map(|i| i.to_string()).unwrap_or_default(),
+            member.avatar_url.clone().unwrap_or_default(),
+            member.role.to_string(),
+        ];
+        wtr.write_record(member.to_csv_record().into_iter().chain(extra))
+            .unwrap();
+    }
+    let bytes = wtr.into_inner().unwrap();
+
+    let mut rdr = csv::Reader::from_reader(bytes.as_slice());
+    let headers = rdr.headers().unwrap().clone();
+    assert_eq!(
+        headers.iter().take(7).collect::<Vec<_>>(),
+        Member::csv_header()
+    );
+
+    let records = rdr.records().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(records.len(), 5);
+    assert_eq!(&records[1][0], "Reader & Writer");
+    assert_eq!(&records[2][1], "明明");
+    assert_eq!(&records[3][6], "未知");
+    assert_eq!(&records[4][0], "Alice, B.");
+    assert_eq!(&records[4][1], "\"爱丽丝\"");
+
+    let parsed = records
+        .iter()
+        .map(|record| Member::from_csv_record(record, &headers))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(parsed, members);
+}
+
+/// Runs the binary on a copy of the fixture, which goes through
+/// `convert_html` and writes a csv file next to it.
+#[test]
+fn converts_html_file_to_csv() {
+    let dir = scratch_dir("converts_html_file_to_csv");
+    let html = dir.join("sample_group.html");
+    fs::write(&html, SAMPLE_GROUP).unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_qq-group-name-extract"))
+        .arg(&html)
+        .args(["--columns", "display_name,qq_number,joined_date"])
+        .current_dir(&dir)
+        .env("XDG_CONFIG_HOME", &dir)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let mut rdr = csv::Reader::from_path(dir.join("sample_group.csv")).unwrap();
+    let headers = rdr.headers().unwrap().clone();
+    assert_eq!(
+        headers.iter().collect::<Vec<_>>(),
+        ["成员", "QQ号", "入群时间"]
+    );
+
+    let records = rdr.records().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(records.len(), 5);
+    assert_eq!(
+        records[0].iter().collect::<Vec<_>>(),
+        ["书虫一号", "100000001", "2014/03/02"]
+    );
+    assert_eq!(
+        records[4].iter().collect::<Vec<_>>(),
+        ["Alice, B.", "100000005", "2021/02/28"]
+    );
+}
+
+#[test]
+fn english_headers_can_be_selected_as_columns() {
+    let dir = scratch_dir("english_headers_can_be_selected_as_columns");
+    let html = dir.join("sample_group.html");
+    fs::write(&html, SAMPLE_GROUP).unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_qq-group-name-extract"))
+        .arg(&html)
+        .args(["--header-lang", "en", "--group-name-from-title"])
+        .args(["--columns", "display_name,group_nickname,last_spoken"])
+        .current_dir(&dir)
+        .env("XDG_CONFIG_HOME", &dir)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let mut rdr = csv::Reader::from_path(dir.join("sample_group.csv")).unwrap();
+    let headers = rdr.headers().unwrap().clone();
+    assert_eq!(
+        headers.iter().collect::<Vec<_>>(),
+        [
+            "group_title",
+            "display_name",
+            "group_nickname",
+            "last_spoken"
+        ]
+    );
+}
+
+#[test]
+fn selects_qq_age_and_last_spoken_columns() {
+    let dir = scratch_dir("selects_qq_age_and_last_spoken_columns");
+    let html = dir.join("sample_group.html");
+    fs::write(&html, SAMPLE_GROUP).unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_qq-group-name-extract"))
+        .arg(&html)
+        .args(["--columns", "qq_number,Q龄,last_spoken"])
+        .current_dir(&dir)
+        .env("XDG_CONFIG_HOME", &dir)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let mut rdr = csv::Reader::from_path(dir.join("sample_group.csv")).unwrap();
+    let headers = rdr.headers().unwrap().clone();
+    assert_eq!(
+        headers.iter().collect::<Vec<_>>(),
+        ["QQ号", "Q龄", "最后发言"]
+    );
+
+    let records = rdr.records().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(
+        records[0].iter().collect::<Vec<_>>(),
+        ["100000001", "15年", "2022/05/18"]
+    );
+    assert_eq!(
+        records[3].iter().collect::<Vec<_>>(),
+        ["100000004", "3年", "未知"]
+    );
+}
+
+#[test]
+fn no_header_starts_with_a_data_row() {
+    let dir = scratch_dir("no_header_starts_with_a_data_row");
+    let html = dir.join("sample_group.html");
+    fs::write(&html, SAMPLE_GROUP).unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_qq-group-name-extract"))
+        .arg(&html)
+        .arg("--no-header")
+        .current_dir(&dir)
+        .env("XDG_CONFIG_HOME", &dir)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let csv = fs::read_to_string(dir.join("sample_group.csv")).unwrap();
+    assert!(csv.starts_with("书虫一号,"), "{csv}");
+    assert_eq!(csv.lines().count(), 5);
+}
+
+#[test]
+fn bom_output_starts_with_bom() {
+    let dir = scratch_dir("bom_output_starts_with_bom");
+    let html = dir.join("sample_group.html");
+    fs::write(&html, SAMPLE_GROUP).unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_qq-group-name-extract"))
+        .arg(&html)
+        .arg("--bom")
+        .current_dir(&dir)
+        .env("XDG_CONFIG_HOME", &dir)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let csv = fs::read(dir.join("sample_group.csv")).unwrap();
+    assert!(csv.starts_with(b"\xEF\xBB\xBF"));
+    // followed by the header, not a second BOM
+    assert!(csv[3..].starts_with("成员,".as_bytes()));
+}
+
+#[test]
+fn parses_every_html_file_in_dir() {
+    let dir = scratch_dir("parses_every_html_file_in_dir");
+    fs::create_dir(dir.join("nested")).unwrap();
+    fs::write(dir.join("sample_group.html"), SAMPLE_GROUP).unwrap();
+    fs::write(dir.join("nested").join("copy.html"), SAMPLE_GROUP).unwrap();
+    fs::write(dir.join("nested").join("empty.html"), "<p>no table</p>").unwrap();
+    fs::write(dir.join("notes.txt"), "not html").unwrap();
+
+    let mut exports = Vec::new();
+    let mut failed = Vec::new();
+    for result in GroupExport::from_dir(&dir) {
+        match result {
+            Ok(export) => exports.push(export),
+            Err(QqExtractError::InFile { path, source }) => {
+                assert!(matches!(*source, QqExtractError::TableNotFound));
+                failed.push(path);
+            }
+            Err(e) => panic!("unexpected error: {e}"),
+        }
+    }
+
+    assert_eq!(exports.len(), 2);
+    for export in &exports {
+        assert_eq!(export.group_id.as_deref(), Some("700000001"));
+        assert_eq!(export.members.len(), 5);
+        assert!(export.source_path.is_some());
+    }
+    assert_eq!(failed, [dir.join("nested").join("empty.html")]);
+}
+
+#[test]
+fn filters_inactive_members() {
+    let members = GroupExport::from_html(SAMPLE_GROUP).unwrap().members;
+    let today = NaiveDate::from_ymd_opt(2022, 12, 31).unwrap();
+    let numbers = |filter: MemberFilter| {
+        members
+            .iter()
+            .filter(|member| filter.matches(member))
+            .map(|member| member.qq_number.as_str())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(numbers(MemberFilter::new(today)).len(), 5);
+    // 小明 last spoke exactly 365 days ago, which isn't more than 365
+    assert_eq!(
+        numbers(MemberFilter::new(today).inactive_since(365)),
+        ["100000004"]
+    );
+    assert_eq!(
+        numbers(MemberFilter::new(today).inactive_since(364)),
+        ["100000003", "100000004"]
+    );
+    assert_eq!(
+        numbers(MemberFilter::new(today).inactive_since(u64::MAX)).len(),
+        1
+    );
+}
+
+#[test]
+fn filters_active_members() {
+    let members = GroupExport::from_html(SAMPLE_GROUP).unwrap().members;
+    let today = NaiveDate::from_ymd_opt(2022, 12, 31).unwrap();
+    let numbers = |filter: MemberFilter| {
+        members
+            .iter()
+            .filter(|member| filter.matches(member))
+            .map(|member| member.qq_number.as_str())
+            .collect::<Vec<_>>()
+    };
+
+    // 小明 last spoke exactly 365 days ago, and 100000004 never has
+    assert_eq!(
+        numbers(MemberFilter::new(today).active_within(365)),
+        ["100000001", "100000002", "100000003", "100000005"]
+    );
+    assert_eq!(
+        numbers(MemberFilter::new(today).active_within(227)),
+        ["100000001", "100000005"]
+    );
+    assert!(numbers(MemberFilter::new(today).active_within(0)).is_empty());
+}
+
+#[test]
+fn filters_members_by_name() {
+    let members = GroupExport::from_html(SAMPLE_GROUP).unwrap().members;
+    let today = NaiveDate::from_ymd_opt(2022, 12, 31).unwrap();
+    let numbers = |filter: MemberFilter| {
+        members
+            .iter()
+            .filter(|member| filter.matches(member))
+            .map(|member| member.qq_number.as_str())
+            .collect::<Vec<_>>()
+    };
+
+    // in either name, ignoring case
+    assert_eq!(
+        numbers(MemberFilter::new(today).name_contains("WRITER")),
+        ["100000002"]
+    );
+    assert_eq!(
+        numbers(MemberFilter::new(today).name_contains("明")),
+        ["100000003"]
+    );
+    assert_eq!(
+        numbers(MemberFilter::new(today).name_contains("爱丽丝")),
+        ["100000005"]
+    );
+
+    let re = Regex::new(r"^[A-Z][a-z]+").unwrap();
+    assert_eq!(
+        numbers(MemberFilter::new(today).name_matches(&re)),
+        ["100000002", "100000005"]
+    );
+    assert_eq!(
+        numbers(
+            MemberFilter::new(today)
+                .name_matches(&re)
+                .inactive_since(227)
+        ),
+        ["100000002"]
+    );
+}
+
+#[test]
+fn filters_members_by_qq_number_range() {
+    let mut members = GroupExport::from_html(SAMPLE_GROUP).unwrap().members;
+    members[4].qq_number = "10000000x".to_owned();
+    let filter = MemberFilter::new(NaiveDate::from_ymd_opt(2022, 12, 31).unwrap())
+        .qq_number_range(100000002, 100000004);
+
+    let numbers = members
+        .iter()
+        .filter(|member| filter.matches(member))
+        .map(|member| member.qq_number.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(numbers, ["100000002", "100000003"]);
+
+    let everything = MemberFilter::new(NaiveDate::from_ymd_opt(2022, 12, 31).unwrap())
+        .qq_number_range(0, u64::MAX);
+    assert!(!everything.matches(&members[4]));
+}
+
+#[test]
+fn filters_members_by_tenure() {
+    let mut members = GroupExport::from_html(SAMPLE_GROUP).unwrap().members;
+    members[3].joined_date = None;
+    let today = NaiveDate::from_ymd_opt(2022, 3, 3).unwrap();
+
+    let years = members
+        .iter()
+        .map(|member| member.joined_years_ago(today))
+        .collect::<Vec<_>>();
+    assert_eq!(years, [Some(8), Some(7), Some(5), None, Some(1)]);
+
+    let numbers = |filter: MemberFilter| {
+        members
+            .iter()
+            .filter(|member| filter.matches(member))
+            .map(|member| member.qq_number.as_str())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        numbers(MemberFilter::new(today).tenure_min(7)),
+        ["100000001", "100000002"]
+    );
+    assert_eq!(
+        numbers(MemberFilter::new(today).tenure_max(5)),
+        ["100000003", "100000005"]
+    );
+    assert_eq!(
+        numbers(MemberFilter::new(today).tenure_min(5).tenure_max(7)),
+        ["100000002", "100000003"]
+    );
+}
+
+#[test]
+fn unique_names_fails_on_duplicates() {
+    let html = SAMPLE_GROUP.replace("<span> 小明 </span>", "<span> 书虫一号 </span>");
+    let export = GroupExport::from_html(&html).unwrap();
+    let duplicates = export.duplicate_names();
+    assert_eq!(duplicates.len(), 1);
+    let numbers = duplicates["书虫一号"]
+        .iter()
+        .map(|member| member.qq_number.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(numbers, ["100000001", "100000003"]);
+
+    let dir = scratch_dir("unique_names_fails_on_duplicates");
+    fs::write(dir.join("duplicates.html"), &html).unwrap();
+    fs::write(dir.join("sample_group.html"), SAMPLE_GROUP).unwrap();
+    let run = |file: &str| {
+        Command::new(env!("CARGO_BIN_EXE_qq-group-name-extract"))
+            .arg(dir.join(file))
+            .arg("--unique-names")
+            .current_dir(&dir)
+            .env("XDG_CONFIG_HOME", &dir)
+            .output()
+            .unwrap()
+    };
+
+    let output = run("duplicates.html");
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("100000001, 100000003"), "{stderr}");
+    // the csv is still written
+    assert!(dir.join("duplicates.csv").is_file());
+
+    assert!(run("sample_group.html").status.success());
+
+    // --anonymize keeps the names and QQ numbers out of the warning too
+    let output = Command::new(env!("CARGO_BIN_EXE_qq-group-name-extract"))
+        .arg(dir.join("duplicates.html"))
+        .args(["--unique-names", "--anonymize"])
+        .current_dir(&dir)
+        .env("XDG_CONFIG_HOME", &dir)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("2 members"), "{stderr}");
+    assert!(!stderr.contains("100000001"), "{stderr}");
+    assert!(!stderr.contains("书虫一号"), "{stderr}");
+}
+
+#[test]
+fn cross_references_groups() {
+    let all = GroupExport::from_html(SAMPLE_GROUP).unwrap();
+    let mut some = all.clone();
+    some.members.retain(|m| m.qq_number != "100000002");
+    let mut others = all.clone();
+    others.members.retain(|m| m.qq_number != "100000004");
+    others.members.reverse();
+
+    let numbers = |groups: &[GroupExport]| {
+        GroupExport::cross_reference(groups)
+            .into_iter()
+            .map(|member| member.qq_number.clone())
+            .collect::<Vec<_>>()
+    };
+    let groups = [all.clone(), some, others];
+    assert_eq!(numbers(&groups), ["100000001", "100000003", "100000005"]);
+    assert_eq!(numbers(&groups[..1]).len(), 5);
+    assert!(numbers(&[]).is_empty());
+}
+
+#[test]
+fn config_flags_yield_to_command_line() {
+    let dir = scratch_dir("config_flags_yield_to_command_line");
+    fs::write(dir.join("sample_group.html"), SAMPLE_GROUP).unwrap();
+    fs::write(dir.join("config.toml"), "append = true\n").unwrap();
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_qq-group-name-extract"))
+            .arg(dir.join("sample_group.html"))
+            .args(args)
+            .current_dir(&dir)
+            .env("XDG_CONFIG_HOME", &dir)
+            .output()
+            .unwrap()
+    };
+
+    // `append` from the config file still conflicts with `--no-header`
+    let output = run(&["--no-header"]);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("`--no-header` can't be used with `--append`"),
+        "{stderr}"
+    );
+    assert!(!dir.join("sample_group.csv").exists());
+
+    // unless the command line turns it off
+    assert!(run(&["--no-header", "--append=false"]).status.success());
+    let csv = fs::read_to_string(dir.join("sample_group.csv")).unwrap();
+    assert_eq!(csv.lines().count(), 5);
+}
+
+#[test]
+fn output_needs_a_single_job() {
+    let dir = scratch_dir("output_needs_a_single_job");
+    fs::write(dir.join("sample_group.html"), SAMPLE_GROUP).unwrap();
+    fs::write(dir.join("config.toml"), "jobs = 2\n").unwrap();
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_qq-group-name-extract"))
+            .arg(dir.join("sample_group.html"))
+            .args(args)
+            .current_dir(&dir)
+            .env("XDG_CONFIG_HOME", &dir)
+            .output()
+            .unwrap()
+    };
+
+    // `jobs` from the config file counts too, and stdout is no different
+    for output in ["out.csv", "-"] {
+        let output = run(&["--output", output]);
+        assert_eq!(output.status.code(), Some(1));
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            stderr.contains("`--output` can't be used with `--jobs` other than 1"),
+            "{stderr}"
+        );
+    }
+    assert!(!dir.join("out.csv").exists());
+
+    assert!(run(&["--output", "out.csv", "--jobs", "1"])
+        .status
+        .success());
+    assert!(dir.join("out.csv").exists());
+}
+
+#[test]
+fn fail_fast_can_be_turned_off_in_config() {
+    let dir = scratch_dir("fail_fast_can_be_turned_off_in_config");
+    fs::write(dir.join("bad.html"), "<html><body></body></html>").unwrap();
+    fs::write(dir.join("sample_group.html"), SAMPLE_GROUP).unwrap();
+    fs::write(dir.join("config.toml"), "fail-fast = false\n").unwrap();
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_qq-group-name-extract"))
+            .arg(dir.join("bad.html"))
+            .arg(dir.join("sample_group.html"))
+            .args(args)
+            .current_dir(&dir)
+            .env("XDG_CONFIG_HOME", &dir)
+            .output()
+            .unwrap()
+    };
+
+    // every file is tried, and the failure reported at the end
+    let output = run(&[]);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Failed to convert 1 file(s)"), "{stderr}");
+    assert!(dir.join("sample_group.csv").exists());
+
+    // a bare `--fail-fast` turns it back on
+    fs::remove_file(dir.join("sample_group.csv")).unwrap();
+    let output = run(&["--fail-fast"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(!dir.join("sample_group.csv").exists());
+}
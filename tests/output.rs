@@ -0,0 +1,119 @@
+use std::{fs::File, io::Cursor, path::PathBuf};
+
+use arrow2::{
+    array::{Int32Array, Utf8Array},
+    datatypes::DataType,
+    io::ipc::read::{read_file_metadata, FileReader as IpcReader},
+};
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use qq_group_name_extract::{
+    group::GroupExport,
+    output::{arrow::write_arrow_ipc, ndjson::write_ndjson, parquet::write_parquet},
+    qqtable::Member,
+};
+
+/// Two members as exported from `https://qun.qq.com/member.html`: the owner
+/// with markup in their name, and one without a known join date.
+const MEMBER_PAGE: &str = r#"<html><head><title>群成员 - 测试群 - 12345678</title></head><body>
+<table id="groupMember">
+<tr><th></th><th>序号</th><th>成员</th><th>群昵称</th><th>QQ号</th><th>性别</th><th>Q龄</th><th>入群时间</th><th>最后发言</th><th></th></tr>
+<tr class="mb"><td></td><td>1</td><td><a class="group-master-a"><i class="icon-group-master"></i></a>
+<img class="" id="useIcon1452313818" src="//q4.qlogo.cn/g?b=qq&amp;nk=1452313818&amp;s=140">
+<span> A&lt;B </span></td><td><span class="white"> </span></td><td>1452313818</td><td>男</td><td>11年</td><td>2018/02/26</td><td>2021/11/01</td><td></td></tr>
+<tr class="mb"><td></td><td>2</td><td><img src="//q4.qlogo.cn/g?b=qq&amp;nk=10001&amp;s=140"><span>Tom &amp; Jerry</span></td><td><span class="white"><span>猫</span></span></td><td>10001</td><td>女</td><td>20年</td><td>未知</td><td>2022/01/05</td><td></td></tr>
+</table></body></html>"#;
+
+#[test]
+fn csv_output() {
+    let members = GroupExport::from_html(MEMBER_PAGE).unwrap().members;
+
+    let mut wtr = csv::Writer::from_writer(Vec::new());
+    wtr.write_record(Member::csv_header()).unwrap();
+    for member in &members {
+        wtr.write_record(member.to_csv_record()).unwrap();
+    }
+    let csv = String::from_utf8(wtr.into_inner().unwrap()).unwrap();
+
+    insta::assert_snapshot!(csv);
+}
+
+#[test]
+fn parse_html_into_matches_csv_output() {
+    let mut csv = Vec::new();
+    Member::parse_html_into(MEMBER_PAGE, &mut csv).unwrap();
+
+    insta::assert_snapshot!("csv_output", String::from_utf8(csv).unwrap());
+}
+
+#[test]
+fn ndjson_output() {
+    let members = GroupExport::from_html(MEMBER_PAGE).unwrap().members;
+    let mut bytes = Vec::new();
+    write_ndjson(&members, &mut bytes).unwrap();
+    let ndjson = String::from_utf8(bytes).unwrap();
+
+    assert!(ndjson.ends_with('\n'));
+    let values = ndjson
+        .lines()
+        .map(serde_json::from_str::<serde_json::Value>)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(values.len(), 2);
+    assert_eq!(values[0]["qq_name"], "A<B");
+    assert_eq!(values[1]["qq_number"], "10001");
+}
+
+#[test]
+fn parquet_output() {
+    let members = GroupExport::from_html(MEMBER_PAGE).unwrap().members;
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("parquet_output.parquet");
+    write_parquet(&members, &path).unwrap();
+
+    let reader = SerializedFileReader::new(File::open(&path).unwrap()).unwrap();
+    assert_eq!(reader.metadata().num_row_groups(), 1);
+    let rows = reader
+        .get_row_iter(None)
+        .unwrap()
+        .map(|row| row.unwrap().to_string())
+        .collect::<Vec<_>>();
+
+    insta::assert_snapshot!(rows.join("\n"));
+}
+
+#[test]
+fn arrow_ipc_output() {
+    let members = GroupExport::from_html(MEMBER_PAGE).unwrap().members;
+    let mut bytes = Vec::new();
+    write_arrow_ipc(&members, &mut bytes).unwrap();
+
+    let mut cursor = Cursor::new(bytes);
+    let metadata = read_file_metadata(&mut cursor).unwrap();
+    let fields = metadata
+        .schema
+        .fields
+        .iter()
+        .map(|field| (field.name.as_str(), field.data_type.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(fields.len(), 10);
+    assert_eq!(fields[1], ("qq_name", DataType::Utf8));
+    assert_eq!(fields[6], ("joined_date", DataType::Date32));
+
+    let chunks = IpcReader::new(cursor, metadata, None, None)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(chunks.len(), 1);
+    let columns = chunks[0].columns();
+    assert_eq!(chunks[0].len(), 2);
+
+    let qq_numbers = columns[3]
+        .as_any()
+        .downcast_ref::<Utf8Array<i32>>()
+        .unwrap();
+    assert_eq!(
+        qq_numbers.values_iter().collect::<Vec<_>>(),
+        ["1452313818", "10001"]
+    );
+    let joined = columns[6].as_any().downcast_ref::<Int32Array>().unwrap();
+    // 2018/02/26, and unknown
+    assert_eq!(joined.iter().collect::<Vec<_>>(), [Some(&17588), None]);
+}
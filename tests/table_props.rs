@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+
+use proptest::prelude::*;
+use qq_group_name_extract::table::{Headers, Table};
+
+/// Header names mapped to distinct column positions below `width`.
+fn headers(width: usize) -> impl Strategy<Value = HashMap<String, usize>> {
+    let positions = Just((0..width).collect::<Vec<_>>()).prop_shuffle();
+    (
+        prop::collection::hash_set("[a-z]{1,8}", 0..=width),
+        positions,
+    )
+        .prop_map(|(names, positions)| names.into_iter().zip(positions).collect::<HashMap<_, _>>())
+}
+
+fn table_data() -> impl Strategy<Value = (HashMap<String, usize>, Vec<Vec<String>>)> {
+    (0..6usize).prop_flat_map(|width| {
+        let rows =
+            prop::collection::vec(prop::collection::vec(any::<String>(), 0..=width + 1), 0..8);
+        (headers(width), rows)
+    })
+}
+
+/// Cell text that needs no escaping inside HTML.
+fn cell_text() -> impl Strategy<Value = String> {
+    "[a-zA-Z0-9]{1,10}"
+}
+
+proptest! {
+    #[test]
+    fn rows_match_data((header_map, data) in table_data()) {
+        let table = Table::from_data(Headers::from(header_map.clone()), data.clone());
+
+        let in_order = table.headers_in_order();
+        prop_assert_eq!(in_order.len(), header_map.len());
+        prop_assert!(in_order.windows(2).all(|w| w[0].1 < w[1].1));
+        for (name, i) in in_order {
+            prop_assert_eq!(header_map.get(name), Some(&i));
+        }
+
+        prop_assert_eq!(table.row_count(), data.len());
+        prop_assert_eq!(table.iter().count(), data.len());
+        for (row, cells) in table.iter().zip(&data) {
+            prop_assert_eq!(row.as_slice(), cells.as_slice());
+            prop_assert_eq!(row.len(), cells.len());
+            for (header, &i) in &header_map {
+                prop_assert_eq!(row.get(header), cells.get(i).map(String::as_str));
+            }
+            prop_assert_eq!(row.get("no such header!"), None);
+
+            let pairs: Vec<_> = row.zip_with_headers().collect();
+            prop_assert_eq!(pairs.len(), cells.len());
+            for (i, (name, cell)) in pairs.into_iter().enumerate() {
+                prop_assert_eq!(cell, cells[i].as_str());
+                match header_map.iter().find(|(_, &j)| j == i) {
+                    Some((header, _)) => prop_assert_eq!(name, header.as_str()),
+                    None => prop_assert_eq!(name, ""),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn find_first_parses_well_formed_tables(
+        header_row in prop::collection::vec(cell_text(), 1..6),
+        rows in prop::collection::vec(prop::collection::vec(cell_text(), 0..6), 0..8),
+    ) {
+        let mut html = String::from("<table><tr>");
+        for header in &header_row {
+            html.push_str(&format!("<th>{}</th>", header));
+        }
+        html.push_str("</tr>");
+        for row in &rows {
+            html.push_str("<tr>");
+            for cell in row {
+                html.push_str(&format!("<td>{}</td>", cell));
+            }
+            html.push_str("</tr>");
+        }
+        html.push_str("</table>");
+
+        let table = Table::find_first(&html);
+        prop_assert!(table.is_some());
+        prop_assert_eq!(table.unwrap().row_count(), rows.len());
+    }
+}